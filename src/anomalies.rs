@@ -0,0 +1,166 @@
+use data::{Frame, FRAME_ALIGNMENT};
+use decode::align;
+use failure::Error;
+use msgpack::*;
+use std::collections::HashMap;
+use EventType;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameRef {
+    pub segment: String,
+    pub position: u64,
+    pub key: u64,
+    #[serde(rename = "type")]
+    pub event_type: EventType,
+    pub state: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AnomalyKind {
+    PositionRegression,
+    DuplicatePosition,
+    DuplicateKeyState,
+    PositionGap,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub message: String,
+    /// The earlier frame first, then the frame at which the anomaly was detected.
+    pub frames: Vec<FrameRef>,
+}
+
+/// Finds position anomalies in the frames of a partition, added in log order.
+///
+/// Positions are expected to increase strictly. The upper 32 bits of a position change with the
+/// segment and the lower 32 bits grow with the offset of the frame in it, so the next frame of a
+/// segment is expected at the position plus the aligned frame length, and the first frame of the
+/// next segment at the next multiple of 2^32. A position which occurred before is only reported as
+/// a duplicate, not also as a regression. Events, but not commands or rejections, are expected to
+/// occur once per key and state, except for the task and payload states which can repeat.
+#[derive(Debug, Default)]
+pub struct AnomalyDetector {
+    pub frames: usize,
+    pub anomalies: Vec<Anomaly>,
+    previous: Option<(FrameRef, usize)>,
+    positions: HashMap<u64, FrameRef>,
+    key_states: HashMap<(u64, u8, &'static str), FrameRef>,
+}
+
+impl AnomalyDetector {
+    pub fn new() -> Self {
+        AnomalyDetector::default()
+    }
+
+    pub fn add(&mut self, segment: &str, frame: &Frame) -> Result<(), Error> {
+        let event_type: EventType = frame.entry.metadata.event_type.into();
+        let event = Event::decode(&event_type, frame.entry.event)?;
+        let current = FrameRef {
+            segment: segment.to_string(),
+            position: frame.entry.log_entry.position,
+            key: frame.entry.log_entry.key,
+            event_type,
+            state: event.as_ref().and_then(|e| e.state()),
+        };
+        let length = frame.data_frame.length as usize;
+        let position = current.position;
+
+        self.frames += 1;
+
+        let previous = self.previous.take();
+        if let Some(earlier) = self.positions.get(&position).cloned() {
+            self.anomaly(
+                AnomalyKind::DuplicatePosition,
+                format!("Position {} occurs more than once", position),
+                earlier,
+                current.clone(),
+            );
+        } else {
+            self.positions.insert(position, current.clone());
+            if let Some((previous, previous_length)) = previous {
+                self.check_order(&previous, previous_length, &current);
+            }
+        }
+
+        if let (Some(event), Some(state)) = (event, current.state) {
+            if !event.is_command() && !event.is_rejection() && !is_repeatable(&event) {
+                let key = (current.key, frame.entry.metadata.event_type, state);
+                if let Some(earlier) = self.key_states.get(&key).cloned() {
+                    self.anomaly(
+                        AnomalyKind::DuplicateKeyState,
+                        format!("Key {} reached state {} more than once", current.key, state),
+                        earlier,
+                        current.clone(),
+                    );
+                } else {
+                    self.key_states.insert(key, current.clone());
+                }
+            }
+        }
+
+        self.previous = Some((current, length));
+        Ok(())
+    }
+
+    fn check_order(&mut self, previous: &FrameRef, previous_length: usize, current: &FrameRef) {
+        if current.position < previous.position {
+            self.anomaly(
+                AnomalyKind::PositionRegression,
+                format!(
+                    "Position {} is lower than the previous position {}",
+                    current.position,
+                    previous.position
+                ),
+                previous.clone(),
+                current.clone(),
+            );
+            return;
+        }
+
+        let expected = if current.segment == previous.segment {
+            previous.position + align(previous_length, FRAME_ALIGNMENT) as u64
+        } else {
+            ((previous.position >> 32) + 1) << 32
+        };
+        if current.position != expected {
+            let message = if current.segment == previous.segment {
+                format!(
+                    "Expected position {} after position {}, found {}",
+                    expected,
+                    previous.position,
+                    current.position
+                )
+            } else {
+                format!(
+                    "Expected position {} at the start of segment {}, found {}",
+                    expected,
+                    current.segment,
+                    current.position
+                )
+            };
+            self.anomaly(AnomalyKind::PositionGap, message, previous.clone(), current.clone());
+        }
+    }
+
+    fn anomaly(&mut self, kind: AnomalyKind, message: String, earlier: FrameRef, current: FrameRef) {
+        self.anomalies.push(Anomaly {
+            kind,
+            message,
+            frames: vec![earlier, current],
+        });
+    }
+}
+
+fn is_repeatable(event: &Event) -> bool {
+    match *event {
+        Event::Task(ref e) => match e.state {
+            TaskState::Locked | TaskState::LockExpired | TaskState::Failed | TaskState::RetriesUpdated => true,
+            _ => false,
+        },
+        Event::WorkflowInstance(ref e) => e.state == WorkflowInstanceState::PayloadUpdated,
+        Event::Workflow(_) => false,
+    }
+}
@@ -0,0 +1,131 @@
+extern crate failure;
+#[macro_use]
+extern crate serde_json;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::prelude::*;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::LogStream;
+use zeebe_log_reader::rejections::*;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-analyze-rejections",
+            about = "Pair rejections with the rejected commands and break them down by type, workflow and client")]
+struct Opt {
+    #[structopt(short = "j", long = "json", help = "Output the report as JSON")]
+    json: bool,
+    #[structopt(help = "Input files")]
+    input: Vec<String>,
+}
+
+fn main() {
+    match try_main() {
+        Ok(_) => {}
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+fn try_main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+
+    let mut analysis = RejectionAnalysis::new();
+
+    for filename in opt.input.iter() {
+        let mut file = File::open(&filename)?;
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        let logstream = LogStream::new(&buffer)?;
+
+        for frame in logstream {
+            analysis.add(&frame)?;
+        }
+    }
+
+    let rejections = analysis.rejections();
+
+    if opt.json {
+        let by_request_stream: BTreeMap<String, usize> = analysis
+            .by_request_stream()
+            .into_iter()
+            .map(|(stream, count)| (stream.to_string(), count))
+            .collect();
+        let report = json!({
+            "byType": analysis.by_type(),
+            "byWorkflow": analysis.by_workflow(),
+            "byRequestStream": by_request_stream,
+            "rejections": rejections,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("Rejections: {}", rejections.len());
+
+    println!();
+    println!("By type:");
+    for (state, count) in analysis.by_type() {
+        println!("  {:<40} {:>8}", state, count);
+    }
+
+    println!();
+    println!("By workflow:");
+    for (bpmn_process_id, count) in analysis.by_workflow() {
+        let bpmn_process_id = if bpmn_process_id.is_empty() { "-" } else { &bpmn_process_id };
+        println!("  {:<40} {:>8}", bpmn_process_id, count);
+    }
+
+    println!();
+    println!("By request stream:");
+    for (request_stream_id, count) in analysis.by_request_stream() {
+        println!("  {:<40} {:>8}", request_stream_id, count);
+    }
+
+    println!();
+    println!("By request:");
+    for ((request_stream_id, request_id), rejections) in analysis.by_request() {
+        println!("  stream {} request {}:", request_stream_id, request_id);
+        for rejection in rejections {
+            print_rejection(&rejection);
+        }
+    }
+
+    let unmatched: Vec<&Rejection> = rejections.iter().filter(|r| r.request().is_none()).collect();
+    if !unmatched.is_empty() {
+        println!();
+        println!("Without client request:");
+        for rejection in unmatched {
+            print_rejection(rejection);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_rejection(rejection: &Rejection) {
+    let command = match rejection.command {
+        Some(ref command) => format!(
+            "{} at position {}",
+            command.state.unwrap_or("unknown command"),
+            command.position
+        ),
+        None => "command not found".to_string(),
+    };
+    println!(
+        "    {} {:?} {} (key {}) rejected {}",
+        rejection.rejection.position,
+        rejection.rejection.event_type,
+        rejection.rejection.state.unwrap_or(""),
+        rejection.rejection.key,
+        command
+    );
+}
@@ -0,0 +1,120 @@
+#[macro_use]
+extern crate failure;
+#[macro_use]
+extern crate serde_json;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::fs::File;
+use std::io::prelude::*;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::LogStream;
+use zeebe_log_reader::tasks::*;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-analyze-tasks",
+            about = "Analyze task lifecycles to find exhausted retries, expired locks and unfinished tasks")]
+struct Opt {
+    #[structopt(short = "j", long = "json", help = "Output the report as JSON")]
+    json: bool,
+    #[structopt(short = "e", long = "lock-expirations", help = "Report tasks whose lock expired at least this often",
+                default_value = "2")]
+    lock_expirations: usize,
+    #[structopt(help = "Input files")]
+    input: Vec<String>,
+}
+
+fn main() {
+    match try_main() {
+        Ok(_) => {}
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+fn try_main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+    if opt.lock_expirations == 0 {
+        bail!("The number of lock expirations has to be at least 1");
+    }
+
+    let mut analysis = TaskAnalysis::new();
+
+    for filename in opt.input.iter() {
+        let mut file = File::open(&filename)?;
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        let logstream = LogStream::new(&buffer)?;
+
+        for frame in logstream {
+            analysis.add(&frame)?;
+        }
+    }
+
+    let totals = analysis.totals();
+    let retries_exhausted = analysis.retries_exhausted();
+    let lock_expirations = analysis.repeated_lock_expirations(opt.lock_expirations);
+    let unfinished = analysis.unfinished();
+
+    if opt.json {
+        let report = json!({
+            "totals": totals,
+            "retriesExhausted": retries_exhausted,
+            "repeatedLockExpirations": lock_expirations,
+            "unfinished": unfinished,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!(
+        "{:<30} {:>8} {:>8} {:>8} {:>8} {:>9} {:>8} {:>9} {:>10}",
+        "task type", "tasks", "locks", "expired", "failed", "completed", "canceled", "exhausted", "unfinished"
+    );
+    for (task_type, total) in &totals {
+        println!(
+            "{:<30} {:>8} {:>8} {:>8} {:>8} {:>9} {:>8} {:>9} {:>10}",
+            task_type,
+            total.tasks,
+            total.locks,
+            total.lock_expirations,
+            total.failures,
+            total.completed,
+            total.canceled,
+            total.retries_exhausted,
+            total.unfinished
+        );
+    }
+
+    print_tasks("Retries exhausted", &retries_exhausted);
+    print_tasks(
+        &format!("Lock expired at least {} times", opt.lock_expirations),
+        &lock_expirations,
+    );
+    print_tasks("Never completed", &unfinished);
+
+    Ok(())
+}
+
+fn print_tasks(title: &str, tasks: &[&TaskLifecycle]) {
+    println!();
+    println!("{}: {}", title, tasks.len());
+    for task in tasks {
+        let states: Vec<&str> = task.transitions.iter().map(|t| t.state.as_str()).collect();
+        println!(
+            "  {} {} (workflow instance {}, activity {}, retries {}): {}",
+            task.key,
+            task.task_type,
+            task.workflow_instance_key,
+            task.activity_id,
+            task.retries().unwrap_or(0),
+            states.join(" -> ")
+        );
+    }
+}
@@ -0,0 +1,142 @@
+extern crate failure;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::fs::File;
+use std::io::prelude::*;
+use std::process;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::LogStream;
+use zeebe_log_reader::check::*;
+use zeebe_log_reader::data::{fs_log_segment, set_fs_log_segment_size, BLOCK_SIZE};
+use zeebe_log_reader::orphans::*;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-check-log", about = "Check the integrity of Zeebe log segments")]
+struct Opt {
+    #[structopt(short = "q", long = "quiet", help = "Only report violations")]
+    quiet: bool,
+    #[structopt(short = "r", long = "references",
+                help = "Also check source event references and find commands without follow-up events")]
+    references: bool,
+    #[structopt(help = "Input files")]
+    input: Vec<String>,
+}
+
+fn main() {
+    match try_main() {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(2);
+        }
+    }
+}
+
+fn try_main() -> Result<bool, Error> {
+    let opt = Opt::from_args();
+
+    let mut valid = true;
+    let mut orphans = OrphanCheck::new();
+
+    for filename in opt.input.iter() {
+        let mut file = File::open(&filename)?;
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        let check = check_segment(&buffer);
+
+        for violation in &check.violations {
+            match violation.position {
+                Some(position) => println!(
+                    "{}: offset {} (position {}): {}",
+                    filename,
+                    violation.offset,
+                    position,
+                    violation.message
+                ),
+                None => println!("{}: offset {}: {}", filename, violation.offset, violation.message),
+            }
+        }
+
+        if !opt.quiet {
+            println!(
+                "{}: {} frames checked, {} violations",
+                filename,
+                check.frames,
+                check.violations.len()
+            );
+        }
+
+        valid &= check.is_valid();
+
+        if opt.references {
+            add_references(&mut orphans, filename, &buffer, &check)?;
+        }
+    }
+
+    if opt.references {
+        let orphans = orphans.orphans();
+        for orphan in &orphans {
+            let node = orphan.node;
+            let message = match orphan.kind {
+                OrphanKind::MissingSource => format!(
+                    "source event position {} is not part of the log",
+                    node.source_event_position.unwrap_or(0)
+                ),
+                OrphanKind::LaterSource => format!(
+                    "source event position {} is not before the record",
+                    node.source_event_position.unwrap_or(0)
+                ),
+                OrphanKind::UnansweredCommand => "command has no follow-up event".to_string(),
+            };
+            println!(
+                "position {} ({:?} {}): {}",
+                node.position,
+                node.event_type,
+                node.state.unwrap_or(""),
+                message
+            );
+        }
+
+        if !opt.quiet {
+            println!("{} records with broken references or unanswered commands", orphans.len());
+        }
+
+        valid &= orphans.is_empty();
+    }
+
+    Ok(valid)
+}
+
+/// Adds the frames up to the end of the structurally valid part of a segment to the reference check,
+/// the frames after it can't be read reliably.
+fn add_references(orphans: &mut OrphanCheck, filename: &str, buffer: &[u8], check: &SegmentCheck) -> Result<(), Error> {
+    if check.valid_end < BLOCK_SIZE {
+        println!("{}: skipping the reference check of a segment with a broken header", filename);
+        return Ok(());
+    }
+    if check.valid_end < fs_log_segment(buffer)?.size as usize {
+        println!(
+            "{}: checking references only up to offset {}, the frames after it are broken",
+            filename,
+            check.valid_end
+        );
+    }
+
+    let mut valid = buffer[..check.valid_end].to_vec();
+    set_fs_log_segment_size(&mut valid, check.valid_end as u32)?;
+    for frame in LogStream::new(&valid)? {
+        // events which can't be decoded are already reported as violations
+        let _ = orphans.add(&frame);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+extern crate failure;
+#[macro_use]
+extern crate serde_json;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::fs::File;
+use std::io::prelude::*;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::LogStream;
+use zeebe_log_reader::causality::Node;
+use zeebe_log_reader::requests::*;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-client-requests",
+            about = "Group records by client request and show statistics per request stream")]
+struct Opt {
+    #[structopt(short = "j", long = "json", help = "Output the requests and statistics as JSON")]
+    json: bool,
+    #[structopt(short = "s", long = "stream", help = "Only show requests of this request stream")]
+    stream: Option<i32>,
+    #[structopt(long = "summary", help = "Only show the request stream statistics")]
+    summary: bool,
+    #[structopt(help = "Input files")]
+    input: Vec<String>,
+}
+
+fn main() {
+    match try_main() {
+        Ok(_) => {}
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+fn try_main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+
+    let mut analysis = RequestAnalysis::new();
+
+    for filename in opt.input.iter() {
+        let mut file = File::open(&filename)?;
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        let logstream = LogStream::new(&buffer)?;
+
+        for frame in logstream {
+            analysis.add(&frame)?;
+        }
+    }
+
+    let requests: Vec<ClientRequest> = if opt.summary {
+        Vec::new()
+    } else {
+        analysis
+            .requests()
+            .into_iter()
+            .filter(|r| opt.stream.map(|s| s == r.request_stream_id).unwrap_or(true))
+            .collect()
+    };
+
+    if opt.json {
+        let streams: Vec<_> = analysis
+            .request_streams()
+            .iter()
+            .map(|(id, statistics)| json!({ "requestStreamId": id, "statistics": statistics }))
+            .collect();
+        let report = if opt.summary {
+            json!({ "requestStreams": streams })
+        } else {
+            json!({ "requests": requests, "requestStreams": streams })
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    for request in &requests {
+        println!(
+            "stream {} request {}{}:",
+            request.request_stream_id,
+            request.request_id,
+            if request.rejected { " (rejected)" } else { "" }
+        );
+        for record in &request.records {
+            print_node("request", record);
+        }
+        if let Some(response) = request.response {
+            print_node("response", response);
+        }
+        if !request.follow_ups.is_empty() {
+            let last = request.follow_ups[request.follow_ups.len() - 1];
+            println!(
+                "  {:<10} {} records up to position {}",
+                "follow-ups",
+                request.follow_ups.len(),
+                last.position
+            );
+        }
+    }
+
+    if !requests.is_empty() {
+        println!();
+    }
+
+    println!(
+        "{:>8} {:>10} {:>12} {:>10} {:>10} {:>10} {:>13}  positions",
+        "stream",
+        "frames",
+        "bytes",
+        "requests",
+        "commands",
+        "rejections",
+        "subscriptions"
+    );
+    for (request_stream_id, statistics) in analysis.request_streams() {
+        println!(
+            "{:>8} {:>10} {:>12} {:>10} {:>10} {:>10} {:>13}  {} - {}",
+            request_stream_id,
+            statistics.frames,
+            statistics.bytes,
+            statistics.requests,
+            statistics.commands,
+            statistics.rejections,
+            statistics.subscriptions.len(),
+            statistics.first_position.unwrap_or(0),
+            statistics.last_position.unwrap_or(0)
+        );
+    }
+
+    Ok(())
+}
+
+fn print_node(label: &str, node: &Node) {
+    println!(
+        "  {:<10} {} {:?} {} (key {})",
+        label,
+        node.position,
+        node.event_type,
+        node.state.unwrap_or(""),
+        node.key
+    );
+}
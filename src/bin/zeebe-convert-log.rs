@@ -1,3 +1,4 @@
+#[macro_use]
 extern crate failure;
 extern crate zeebe_log_reader;
 extern crate structopt;
@@ -13,9 +14,11 @@ use structopt::StructOpt;
 
 use zeebe_log_reader::LogStream;
 use zeebe_log_reader::output::*;
+use zeebe_log_reader::query::Query;
+use zeebe_log_reader::redact::Redaction;
 
 #[derive(StructOpt, Debug)]
-#[structopt(name = "zeebe-convert-log", about = "Convert Zeebe log streams to plain text output")]
+#[structopt(name = "zeebe-convert-log", about = "Convert Zeebe log streams to plain text, JSON or tabular output")]
 struct Opt {
     #[structopt(short = "c", long = "console", help = "Output to console instead of file")]
     console: bool,
@@ -23,6 +26,26 @@ struct Opt {
     input: Vec<String>,
     #[structopt(short = "f", long = "filter", help = "Filter event type")]
     filter: Option<u8>,
+    #[structopt(short = "p", long = "payload", help = "Payload output: full, omit or number of bytes to truncate to",
+                default_value = "full")]
+    payload: String,
+    #[structopt(short = "r", long = "redact",
+                help = "Redact payloads and custom headers: hash or placeholder")]
+    redact: Option<String>,
+    #[structopt(long = "salt", help = "Secret salt for hashed redaction, a random salt is used by default")]
+    salt: Option<String>,
+    #[structopt(long = "format", help = "Output format: text, json (one record per line), csv or tsv",
+                default_value = "text")]
+    format: String,
+    #[structopt(long = "columns", help = "Columns of csv and tsv output", 
+                default_value = "position,key,type,state,bpmnProcessId,activityId,taskType,retries")]
+    columns: String,
+    #[structopt(short = "t", long = "template",
+                help = "Format each event with a template like '{position} {type} {state} {payload.orderId}'")]
+    template: Option<String>,
+    #[structopt(short = "w", long = "where",
+                help = "Only output events matching the query, e.g. \"type == Task && state == 'LOCKED'\"")]
+    query: Option<String>,
 }
 
 fn main() {
@@ -30,7 +53,7 @@ fn main() {
     match try_main() {
         Ok(_) => {
             let duration = now.elapsed();
-            println!(
+            eprintln!(
                 "Took {}.{:09}s",
                 duration.as_secs(),
                 duration.subsec_nanos()
@@ -44,17 +67,47 @@ fn main() {
 fn try_main() -> Result<(), Error> {
     let opt = Opt::from_args();
 
-    let mut output: Box<EventOutput> = Box::new(StdOutput::new());
+    let payload: PayloadMode = opt.payload.parse()?;
+    let redaction: Option<Redaction> = match opt.redact {
+        Some(ref redact) => Some(Redaction::new(redact, opt.salt.as_ref().map(|s| s.as_str()))?),
+        None if opt.salt.is_some() => bail!("A salt can only be used with --redact"),
+        None => None,
+    };
+    let query = match opt.query {
+        Some(ref query) => Some(Query::parse(query)?),
+        None => None,
+    };
+
+    let mut formatters = Formatters::builtin(payload, parse_columns(&opt.columns));
+
+    let format = match opt.template {
+        Some(ref template) => {
+            let template = TemplateFormatter::new(template)?;
+            formatters.register("template", move || Box::new(template.clone()));
+            "template"
+        }
+        None => opt.format.as_str(),
+    };
+
+    let mut stdout = Output::stdout(formatters.create(format)?);
+    stdout.set_redaction(redaction);
+    let mut output: Box<EventOutput> = Box::new(stdout);
 
     for filename in opt.input.iter() {
         let now = Instant::now();
         let mut file = File::open(&filename)?;
 
         if !opt.console {
-            let output_name = format!("{}.{}", filename, "txt");
+            let extension = match format {
+                "text" | "template" => "txt",
+                f => f,
+            };
+            let output_name = format!("{}.{}", filename, extension);
             print!("Converting {} to {}", filename, output_name);
 
-            output = Box::new(FileOutput::new(&output_name)?);
+            let mut file_output = Output::file(&output_name, formatters.create(format)?)?;
+            file_output.set_redaction(redaction);
+            output = Box::new(file_output);
         }
 
 
@@ -66,6 +119,11 @@ fn try_main() -> Result<(), Error> {
         opt.filter.map(|f| logstream.event_filter(f));
 
         for event in logstream {
+            if let Some(ref query) = query {
+                if !query.matches_frame(&event)? {
+                    continue;
+                }
+            }
             output.output(&event)?;
         }
 
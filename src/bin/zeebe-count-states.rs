@@ -1,29 +1,46 @@
+#[macro_use]
 extern crate failure;
+extern crate serde_json;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
 extern crate zeebe_log_reader;
 
 use failure::Error;
 
-use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 use std::time::Instant;
 
-use zeebe_log_reader::{EventType, LogStream};
-use zeebe_log_reader::msgpack::*;
+use structopt::StructOpt;
+
+use zeebe_log_reader::LogStream;
+use zeebe_log_reader::output;
+use zeebe_log_reader::stats::*;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-count-states", about = "Collect statistics about Zeebe log streams")]
+struct Opt {
+    #[structopt(long = "format", help = "Output format: text, json or csv", default_value = "text")]
+    format: String,
+    #[structopt(help = "Input files")]
+    input: Vec<String>,
+}
 
 fn main() {
     let now = Instant::now();
     match try_main() {
-        Ok(_) => println!("Took {:?}", now.elapsed()),
+        Ok(_) => eprintln!("Took {:?}", now.elapsed()),
         Err(e) => eprintln!("Error: {}", e),
     }
 }
 
 fn try_main() -> Result<(), Error> {
-    let mut workflow_instance_events = HashMap::new();
-    let mut task_events = HashMap::new();
+    let opt = Opt::from_args();
+
+    let mut statistics = Statistics::new();
 
-    for filename in std::env::args().skip(1) {
+    for filename in opt.input.iter() {
         let mut file = File::open(&filename)?;
 
         let mut buffer = Vec::new();
@@ -32,27 +49,85 @@ fn try_main() -> Result<(), Error> {
         let logstream = LogStream::new(&buffer)?;
 
         for frame in logstream {
-            match frame.entry.metadata.event_type.into() {
-                EventType::Task => {
-                    let e: TaskEvent = deserialize(frame.entry.event)?;
-                    *task_events.entry(e.state).or_insert(0) += 1;
-                },
-                EventType::WorkflowInstance => {
-                    let e: WorkflowInstanceEvent = deserialize(frame.entry.event)?;
-                    *workflow_instance_events.entry(e.state).or_insert(0) += 1
-                },
-                _ => {}
-            }
+            statistics.add(filename, &frame)?;
         }
     }
 
-    for (state, count) in workflow_instance_events {
-        println!("{:?}: {}", state, count);
+    match opt.format.as_str() {
+        "text" => print_text(&statistics),
+        "json" => println!("{}", serde_json::to_string_pretty(&statistics)?),
+        "csv" => print_csv(&statistics),
+        f => bail!("Unknown output format '{}', expected text, json or csv", f),
     }
 
-    for (state, count) in task_events {
-        println!("{:?}: {}", state, count);
+    Ok(())
+}
+
+fn print_text(statistics: &Statistics) {
+    println!("Frames: {}", statistics.frames);
+
+    for (name, counts) in statistics.counters() {
+        println!();
+        println!("{}:", name);
+        for (key, count) in counts {
+            println!("  {:<50} {:>10}", key, count);
+        }
     }
 
-    Ok(())
+    print_distribution("eventSize", &statistics.event_sizes);
+    print_distribution("payloadSize", &statistics.payload_sizes);
+
+    println!();
+    println!("segments:");
+    for (segment, totals) in &statistics.segments {
+        println!(
+            "  {:<50} {:>10} frames {:>12} bytes, positions {} - {}",
+            segment,
+            totals.frames,
+            totals.bytes,
+            totals.first_position.unwrap_or(0),
+            totals.last_position.unwrap_or(0)
+        );
+    }
+}
+
+fn print_distribution(name: &str, distribution: &Distribution) {
+    println!();
+    println!(
+        "{}: count {}, min {}, max {}, mean {:.1}",
+        name,
+        distribution.count,
+        distribution.min.unwrap_or(0),
+        distribution.max.unwrap_or(0),
+        distribution.mean().unwrap_or(0.0)
+    );
+    for (bound, count) in &distribution.buckets {
+        println!("  <= {:<47} {:>10}", bound, count);
+    }
+}
+
+fn print_csv(statistics: &Statistics) {
+    println!("category,name,value");
+    println!("total,frames,{}", statistics.frames);
+
+    for (name, counts) in statistics.counters() {
+        for (key, count) in counts {
+            println!("{},{},{}", name, output::escape(&key, ','), count);
+        }
+    }
+
+    for &(name, distribution) in &[("eventSize", &statistics.event_sizes), ("payloadSize", &statistics.payload_sizes)] {
+        println!("{},count,{}", name, distribution.count);
+        println!("{},total,{}", name, distribution.total);
+        println!("{},min,{}", name, distribution.min.unwrap_or(0));
+        println!("{},max,{}", name, distribution.max.unwrap_or(0));
+        for (bound, count) in &distribution.buckets {
+            println!("{},<={},{}", name, bound, count);
+        }
+    }
+
+    for (segment, totals) in &statistics.segments {
+        println!("segmentFrames,{},{}", output::escape(segment, ','), totals.frames);
+        println!("segmentBytes,{},{}", output::escape(segment, ','), totals.bytes);
+    }
 }
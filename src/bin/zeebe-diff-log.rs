@@ -0,0 +1,134 @@
+extern crate failure;
+extern crate serde_json;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::Path;
+use std::process;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::LogStream;
+use zeebe_log_reader::diff::*;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-diff-log", about = "Compare two Zeebe partition logs frame by frame")]
+struct Opt {
+    #[structopt(short = "j", long = "json", help = "Output the differences as JSON")]
+    json: bool,
+    #[structopt(help = "Left log, a segment file or a directory of segments")]
+    left: String,
+    #[structopt(help = "Right log, a segment file or a directory of segments")]
+    right: String,
+}
+
+fn main() {
+    match try_main() {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(2);
+        }
+    }
+}
+
+fn try_main() -> Result<bool, Error> {
+    let opt = Opt::from_args();
+
+    let left = read_segments(&opt.left)?;
+    let right = read_segments(&opt.right)?;
+
+    let left_streams = left.iter().map(|b| LogStream::new(b)).collect::<Result<Vec<_>, _>>()?;
+    let right_streams = right.iter().map(|b| LogStream::new(b)).collect::<Result<Vec<_>, _>>()?;
+
+    let diff = diff(
+        left_streams.into_iter().flatten(),
+        right_streams.into_iter().flatten(),
+    )?;
+
+    if opt.json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(diff.is_equal());
+    }
+
+    println!(
+        "Compared {} frames of {} with {} frames of {}, {} equal",
+        diff.left_frames,
+        opt.left,
+        diff.right_frames,
+        opt.right,
+        diff.equal_frames
+    );
+
+    let first = match diff.first_divergence {
+        Some(ref first) => first,
+        None => {
+            println!("The logs are equal");
+            return Ok(true);
+        }
+    };
+
+    println!("First divergence at position {}: {}", first.position, describe(&first.divergence));
+
+    println!("Differing ranges:");
+    for range in &diff.ranges {
+        let mut parts = Vec::new();
+        if range.only_left > 0 {
+            parts.push(format!("{} only left", range.only_left));
+        }
+        if range.only_right > 0 {
+            parts.push(format!("{} only right", range.only_right));
+        }
+        if !range.changed.is_empty() {
+            parts.push(format!("changed {}", range.changed.iter().cloned().collect::<Vec<_>>().join(", ")));
+        }
+        println!(
+            "  {} - {}: {} frames ({})",
+            range.first_position,
+            range.last_position,
+            range.frames,
+            parts.join("; ")
+        );
+    }
+
+    Ok(false)
+}
+
+fn describe(divergence: &Divergence) -> String {
+    match *divergence {
+        Divergence::OnlyLeft => "frame only in left log".to_string(),
+        Divergence::OnlyRight => "frame only in right log".to_string(),
+        Divergence::Changed(ref parts) => format!("different {}", parts.join(", ")),
+    }
+}
+
+fn read_segments(path: &str) -> Result<Vec<Vec<u8>>, Error> {
+    let mut filenames = Vec::new();
+    if Path::new(path).is_dir() {
+        for entry in fs::read_dir(path)? {
+            let filename = entry?.path();
+            if filename.extension().map(|e| e == "data").unwrap_or(false) {
+                filenames.push(filename);
+            }
+        }
+        filenames.sort();
+    } else {
+        filenames.push(Path::new(path).to_path_buf());
+    }
+
+    let mut segments = Vec::new();
+    for filename in filenames {
+        let mut file = File::open(&filename)?;
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        segments.push(buffer);
+    }
+    Ok(segments)
+}
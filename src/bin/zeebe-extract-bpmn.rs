@@ -0,0 +1,125 @@
+extern crate failure;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::Path;
+use std::process;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::{EventType, LogStream};
+use zeebe_log_reader::msgpack::*;
+use zeebe_log_reader::output;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-extract-bpmn", about = "Extract deployed BPMN resources from Zeebe log streams")]
+struct Opt {
+    #[structopt(short = "o", long = "output", help = "Output directory", default_value = ".")]
+    output: String,
+    #[structopt(help = "Input files")]
+    input: Vec<String>,
+}
+
+struct Deployment {
+    deployment_key: i64,
+    workflow_key: u64,
+    filename: String,
+}
+
+fn main() {
+    match try_main() {
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn try_main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+
+    let directory = Path::new(&opt.output);
+    fs::create_dir_all(directory)?;
+
+    let mut deployments = BTreeMap::new();
+    let mut resources = HashSet::new();
+
+    for filename in opt.input.iter() {
+        let mut file = File::open(&filename)?;
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        let logstream = LogStream::new(&buffer)?;
+
+        for frame in logstream {
+            if let EventType::Workflow = frame.entry.metadata.event_type.into() {
+                let event: WorkflowEvent = deserialize(frame.entry.event)?;
+                // the create command is also written for deployments which are rejected
+                if event.state != WorkflowState::Created {
+                    continue;
+                }
+                let id = (event.bpmn_process_id.clone(), event.version);
+                if deployments.contains_key(&id) {
+                    continue;
+                }
+
+                let name = file_name(&event.bpmn_process_id);
+                let mut resource = format!("{}_v{}.bpmn", name, event.version);
+                if !resources.insert(resource.clone()) {
+                    resource = format!("{}_v{}_{}.bpmn", name, event.version, { frame.entry.log_entry.key });
+                    resources.insert(resource.clone());
+                }
+                File::create(directory.join(&resource))?.write_all(&event.bpmn_xml)?;
+                println!("Extracted {}", resource);
+
+                deployments.insert(
+                    id,
+                    Deployment {
+                        deployment_key: event.deployment_key,
+                        workflow_key: frame.entry.log_entry.key,
+                        filename: resource,
+                    },
+                );
+            }
+        }
+    }
+
+    let mut manifest = File::create(directory.join("manifest.csv"))?;
+    writeln!(manifest, "deploymentKey,workflowKey,bpmnProcessId,version,file")?;
+    for ((bpmn_process_id, version), deployment) in deployments {
+        writeln!(
+            manifest,
+            "{},{},{},{},{}",
+            deployment.deployment_key,
+            deployment.workflow_key,
+            output::escape(&bpmn_process_id, ','),
+            version,
+            output::escape(&deployment.filename, ',')
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Turns a BPMN process id from the log into a plain file name, so it can't point outside the
+/// output directory.
+fn file_name(bpmn_process_id: &str) -> String {
+    let name: String = bpmn_process_id
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+        .collect();
+    let name = name.trim_left_matches('.');
+    if name.is_empty() {
+        "workflow".to_string()
+    } else {
+        name.to_string()
+    }
+}
@@ -0,0 +1,79 @@
+extern crate failure;
+extern crate serde_json;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::fs::File;
+use std::io::prelude::*;
+use std::process;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::LogStream;
+use zeebe_log_reader::anomalies::*;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-find-anomalies",
+            about = "Find position regressions, duplicates and gaps in the segments of a partition")]
+struct Opt {
+    #[structopt(short = "j", long = "json", help = "Output the anomalies as JSON")]
+    json: bool,
+    #[structopt(help = "Input files, in log order")]
+    input: Vec<String>,
+}
+
+fn main() {
+    match try_main() {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(2);
+        }
+    }
+}
+
+fn try_main() -> Result<bool, Error> {
+    let opt = Opt::from_args();
+
+    let mut detector = AnomalyDetector::new();
+
+    for filename in opt.input.iter() {
+        let mut file = File::open(&filename)?;
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        let logstream = LogStream::new(&buffer)?;
+
+        for frame in logstream {
+            detector.add(filename, &frame)?;
+        }
+    }
+
+    if opt.json {
+        println!("{}", serde_json::to_string_pretty(&detector.anomalies)?);
+        return Ok(detector.anomalies.is_empty());
+    }
+
+    for anomaly in &detector.anomalies {
+        println!("{:?}: {}", anomaly.kind, anomaly.message);
+        for frame in &anomaly.frames {
+            println!(
+                "  {} position {} key {} {:?} {}",
+                frame.segment,
+                frame.position,
+                frame.key,
+                frame.event_type,
+                frame.state.unwrap_or("")
+            );
+        }
+    }
+
+    println!("{} frames checked, {} anomalies", detector.frames, detector.anomalies.len());
+
+    Ok(detector.anomalies.is_empty())
+}
@@ -0,0 +1,83 @@
+extern crate failure;
+extern crate serde_json;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::fs::File;
+use std::io::prelude::*;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::LogStream;
+use zeebe_log_reader::timeline::*;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-instance-timeline", about = "Show everything that happened to a workflow instance")]
+struct Opt {
+    #[structopt(short = "j", long = "json", help = "Output the timeline as JSON")]
+    json: bool,
+    #[structopt(help = "Workflow instance key")]
+    workflow_instance_key: i64,
+    #[structopt(help = "Input files")]
+    input: Vec<String>,
+}
+
+fn main() {
+    match try_main() {
+        Ok(_) => {}
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+fn try_main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+
+    let mut timeline = Timeline::new(opt.workflow_instance_key);
+
+    for filename in opt.input.iter() {
+        let mut file = File::open(&filename)?;
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        let logstream = LogStream::new(&buffer)?;
+
+        for frame in logstream {
+            timeline.add(&frame)?;
+        }
+    }
+
+    if timeline.is_empty() {
+        eprintln!("No events found for workflow instance {}", opt.workflow_instance_key);
+    }
+
+    let entries = timeline.finish();
+
+    if opt.json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    for entry in entries {
+        let state = match entry.state {
+            TimelineState::WorkflowInstance(ref state) => format!("{:?}", state),
+            TimelineState::Task(ref state) => format!("Task {:?}", state),
+        };
+        print!("{:>20} {:<30}", entry.position, state);
+        if let Some(ref activity_id) = entry.activity_id {
+            print!(" {}", activity_id);
+        }
+        if let Some(ref task_type) = entry.task_type {
+            print!(" ({})", task_type);
+        }
+        if let Some(ref payload) = entry.payload {
+            print!(" payload: {}", payload);
+        }
+        println!();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+extern crate failure;
+extern crate serde_json;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::fs::File;
+use std::io::prelude::*;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::LogStream;
+use zeebe_log_reader::terms::TermHistory;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-raft-terms", about = "Show the raft term history of a partition log")]
+struct Opt {
+    #[structopt(short = "j", long = "json", help = "Output the term history as JSON")]
+    json: bool,
+    #[structopt(help = "Input files, in log order")]
+    input: Vec<String>,
+}
+
+fn main() {
+    match try_main() {
+        Ok(_) => {}
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+fn try_main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+
+    let mut history = TermHistory::new();
+
+    for filename in opt.input.iter() {
+        let mut file = File::open(&filename)?;
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        let logstream = LogStream::new(&buffer)?;
+
+        for frame in logstream {
+            history.add(filename, &frame)?;
+        }
+    }
+
+    if opt.json {
+        println!("{}", serde_json::to_string_pretty(&history)?);
+        return Ok(());
+    }
+
+    for term in &history.terms {
+        println!(
+            "term {:>5}: positions {} - {} ({} frames, {}){}",
+            term.term,
+            term.first_position,
+            term.last_position,
+            term.frames,
+            term.segment,
+            if term.regression { " REGRESSION" } else { "" }
+        );
+        for configuration in &term.configurations {
+            let members: Vec<String> = configuration
+                .members
+                .iter()
+                .map(|m| format!("{}:{}", m.host, m.port))
+                .collect();
+            println!("  raft configuration at {}: {}", configuration.position, members.join(", "));
+        }
+    }
+
+    println!();
+    println!("The leader of a term is not recorded in the log, raft configurations only list the members.");
+
+    let regressions = history.regressions();
+    if !regressions.is_empty() {
+        println!();
+        println!("{} term regressions:", regressions.len());
+        for (highest, term) in regressions {
+            println!(
+                "  term {} at position {} is lower than term {} starting at position {}",
+                term.term,
+                term.first_position,
+                highest.term,
+                highest.first_position
+            );
+        }
+    }
+
+    Ok(())
+}
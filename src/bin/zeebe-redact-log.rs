@@ -0,0 +1,119 @@
+#[macro_use]
+extern crate failure;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+use std::process;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::{EventType, LogStream};
+use zeebe_log_reader::data::fs_log_segment;
+use zeebe_log_reader::encode::SegmentWriter;
+use zeebe_log_reader::redact::Redaction;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-redact-log",
+            about = "Write a copy of a Zeebe log segment with redacted payloads and custom headers")]
+struct Opt {
+    #[structopt(short = "m", long = "mode", help = "Redaction mode: hash or placeholder", default_value = "hash")]
+    mode: String,
+    #[structopt(short = "s", long = "salt",
+                help = "Secret salt for hashed redaction, a random salt is used by default")]
+    salt: Option<String>,
+    #[structopt(long = "keep-unknown",
+                help = "Copy events of unknown types unchanged instead of failing, they are not redacted")]
+    keep_unknown: bool,
+    #[structopt(short = "f", long = "force", help = "Overwrite the output file if it exists")]
+    force: bool,
+    #[structopt(help = "Input file")]
+    input: String,
+    #[structopt(help = "Output file")]
+    output: String,
+}
+
+fn main() {
+    match try_main() {
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn try_main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+
+    let redaction = Redaction::new(&opt.mode, opt.salt.as_ref().map(|s| s.as_str()))?;
+
+    let output = Path::new(&opt.output);
+    if output.exists() {
+        if Path::new(&opt.input).canonicalize()? == output.canonicalize()? {
+            bail!("The output file has to be different from the input file");
+        }
+        if !opt.force {
+            bail!("Output file {} already exists, use --force to overwrite it", opt.output);
+        }
+    }
+
+    let mut file = File::open(&opt.input)?;
+
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)?;
+
+    let mut writer = SegmentWriter::from_segment(&buffer)?;
+    let mut frames = 0;
+    let mut redacted = 0;
+    let mut unknown = 0;
+
+    for frame in LogStream::new(&buffer)? {
+        let event_type: EventType = frame.entry.metadata.event_type.into();
+        let position = frame.entry.log_entry.position;
+        let event = redaction
+            .encoded_event(&event_type, frame.entry.event)
+            .map_err(|e| format_err!("Unable to redact event at position {}: {}", position, e))?;
+        match event {
+            Some(event) => {
+                if event.as_slice() != frame.entry.event {
+                    redacted += 1;
+                }
+                writer.append_frame_with_event(&frame, &event)?;
+            }
+            None if opt.keep_unknown => {
+                writer.append_frame(&frame)?;
+                unknown += 1;
+            }
+            None => bail!(
+                "Unable to redact event of unknown type {:?} at position {}, use --keep-unknown to copy it unchanged",
+                event_type,
+                position
+            ),
+        }
+        frames += 1;
+    }
+
+    let moved = writer.size() != fs_log_segment(&buffer)?.size as usize;
+    File::create(output)?.write_all(&writer.finish()?)?;
+
+    println!(
+        "Wrote {} frames to {}, {} with redacted fields",
+        frames,
+        opt.output,
+        redacted
+    );
+    if moved {
+        println!("Redacted values changed the frame lengths, positions no longer match the frame offsets");
+    }
+    if unknown > 0 {
+        eprintln!("Warning: copied {} events of unknown types without redacting them", unknown);
+    }
+
+    Ok(())
+}
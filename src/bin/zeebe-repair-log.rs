@@ -0,0 +1,88 @@
+#[macro_use]
+extern crate failure;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+use std::process;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::repair::*;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-repair-log",
+            about = "Write a copy of a Zeebe log segment truncated at the last valid frame")]
+struct Opt {
+    #[structopt(short = "f", long = "force", help = "Overwrite the output file if it exists")]
+    force: bool,
+    #[structopt(help = "Input file")]
+    input: String,
+    #[structopt(help = "Output file")]
+    output: String,
+}
+
+fn main() {
+    match try_main() {
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn try_main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+
+    let output = Path::new(&opt.output);
+    if output.exists() {
+        if Path::new(&opt.input).canonicalize()? == output.canonicalize()? {
+            bail!("The output file has to be different from the input file");
+        }
+        if !opt.force {
+            bail!("Output file {} already exists, use --force to overwrite it", opt.output);
+        }
+    }
+
+    let mut file = File::open(&opt.input)?;
+
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)?;
+
+    let (repaired, report) = repair_segment(&buffer)?;
+
+    File::create(output)?.write_all(&repaired)?;
+
+    if report.is_repaired() {
+        println!("Wrote repaired segment to {}", opt.output);
+        println!("  size: {} -> {} bytes ({} bytes cut)", report.original_size, report.repaired_size, report.cut_bytes);
+        println!("  frames kept: {}, readable frames cut: {}", report.frames_kept, report.frames_cut);
+        match report.last_valid_position {
+            Some(position) => println!("  last valid position: {}", position),
+            None => println!("  no valid frames"),
+        }
+    } else {
+        println!("No structurally invalid frames found, wrote unchanged copy to {}", opt.output);
+    }
+    for violation in &report.violations {
+        let kept = if violation.structural { "" } else { " (frame kept)" };
+        match violation.position {
+            Some(position) => println!(
+                "  offset {} (position {}): {}{}",
+                violation.offset,
+                position,
+                violation.message,
+                kept
+            ),
+            None => println!("  offset {}: {}{}", violation.offset, violation.message, kept),
+        }
+    }
+
+    Ok(())
+}
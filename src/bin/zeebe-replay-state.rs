@@ -0,0 +1,66 @@
+extern crate failure;
+extern crate serde_json;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::fs::File;
+use std::io::prelude::*;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::LogStream;
+use zeebe_log_reader::state::*;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-replay-state",
+            about = "Replay Zeebe log streams and dump the resulting workflow instance state as JSON")]
+struct Opt {
+    #[structopt(short = "i", long = "instance", help = "Only dump the state of this workflow instance")]
+    instance: Option<i64>,
+    #[structopt(short = "a", long = "active", help = "Only dump active workflow instances")]
+    active: bool,
+    #[structopt(help = "Input files")]
+    input: Vec<String>,
+}
+
+fn main() {
+    match try_main() {
+        Ok(_) => {}
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+fn try_main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+
+    let mut engine = StateEngine::new();
+
+    for filename in opt.input.iter() {
+        let mut file = File::open(&filename)?;
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        let logstream = LogStream::new(&buffer)?;
+
+        for frame in logstream {
+            engine.add(&frame)?;
+        }
+    }
+
+    let state = engine.replay()?;
+
+    let instances: Vec<&InstanceState> = state
+        .instances
+        .values()
+        .filter(|i| opt.instance.map_or(true, |key| i.workflow_instance_key == key))
+        .filter(|i| !opt.active || i.status == InstanceStatus::Active)
+        .collect();
+
+    println!("{}", serde_json::to_string_pretty(&instances)?);
+
+    Ok(())
+}
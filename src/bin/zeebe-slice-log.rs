@@ -0,0 +1,126 @@
+#[macro_use]
+extern crate failure;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+use std::process;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::LogStream;
+use zeebe_log_reader::encode::SegmentWriter;
+use zeebe_log_reader::slice::SliceFilter;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-slice-log",
+            about = "Copy a position range or the events of one workflow instance into a new log segment")]
+struct Opt {
+    #[structopt(long = "from", help = "First position to copy")]
+    from: Option<u64>,
+    #[structopt(long = "to", help = "Last position to copy")]
+    to: Option<u64>,
+    #[structopt(short = "i", long = "instance",
+                help = "Copy the events of this workflow instance and the workflow it was created from")]
+    instance: Option<i64>,
+    #[structopt(short = "o", long = "output", help = "Output file")]
+    output: String,
+    #[structopt(short = "f", long = "force", help = "Overwrite the output file if it exists")]
+    force: bool,
+    #[structopt(help = "Input files")]
+    input: Vec<String>,
+}
+
+fn main() {
+    match try_main() {
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn try_main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+
+    if opt.input.is_empty() {
+        bail!("No input files given");
+    }
+    if Path::new(&opt.output).exists() && !opt.force {
+        bail!("Output file {} already exists, use --force to overwrite it", opt.output);
+    }
+
+    let mut filter = match opt.instance {
+        Some(key) => {
+            if opt.from.is_some() || opt.to.is_some() {
+                bail!("Either select a position range or a workflow instance, not both");
+            }
+            SliceFilter::instance(key)
+        }
+        None => {
+            if opt.from.is_none() && opt.to.is_none() {
+                bail!("Select a position range with --from and --to or a workflow instance with --instance");
+            }
+            SliceFilter::range(opt.from, opt.to)
+        }
+    };
+
+    let mut buffers = Vec::new();
+    for filename in opt.input.iter() {
+        let mut file = File::open(&filename)?;
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        buffers.push(buffer);
+    }
+
+    if filter.needs_prepare() {
+        for buffer in &buffers {
+            for frame in LogStream::new(buffer)? {
+                filter.prepare(&frame)?;
+            }
+        }
+    }
+
+    let mut writer = SegmentWriter::from_segment(&buffers[0])?;
+    let mut frames = 0;
+    let mut positions = None;
+
+    for buffer in &buffers {
+        for frame in LogStream::new(buffer)? {
+            if filter.matches(&frame)? {
+                writer.append_frame(&frame)?;
+                frames += 1;
+
+                let position = frame.entry.log_entry.position;
+                positions = Some(match positions {
+                    Some((first, _)) => (first, position),
+                    None => (position, position),
+                });
+            }
+        }
+    }
+
+    let segment = writer.finish()?;
+    File::create(&opt.output)?.write_all(&segment)?;
+
+    match positions {
+        Some((first, last)) => println!(
+            "Wrote {} frames with positions {} - {} ({} bytes) to {}",
+            frames,
+            first,
+            last,
+            segment.len(),
+            opt.output
+        ),
+        None => println!("No frames selected, wrote empty segment to {}", opt.output),
+    }
+
+    Ok(())
+}
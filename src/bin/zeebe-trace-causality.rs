@@ -0,0 +1,105 @@
+#[macro_use]
+extern crate failure;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::prelude::*;
+
+use structopt::StructOpt;
+
+use zeebe_log_reader::LogStream;
+use zeebe_log_reader::causality::*;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-trace-causality",
+            about = "Show the records which caused a record and the records it caused in turn")]
+struct Opt {
+    #[structopt(help = "Position of the record to trace")]
+    position: u64,
+    #[structopt(help = "Input files")]
+    input: Vec<String>,
+}
+
+fn main() {
+    match try_main() {
+        Ok(_) => {}
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+fn try_main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+
+    let mut graph = CausalityGraph::new();
+
+    for filename in opt.input.iter() {
+        let mut file = File::open(&filename)?;
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        let logstream = LogStream::new(&buffer)?;
+
+        for frame in logstream {
+            graph.add(&frame)?;
+        }
+    }
+
+    let node = match graph.node(opt.position) {
+        Some(node) => node,
+        None => bail!("No record found at position {}", opt.position),
+    };
+
+    let mut causes = graph.causes(node.position);
+    causes.reverse();
+
+    let root = causes.first().cloned().unwrap_or(node);
+    if let Some(partition) = root.foreign_source_partition {
+        println!(
+            "(caused by position {} on partition {})",
+            root.source_event_position.unwrap_or(0),
+            partition
+        );
+    } else if let Some(source) = root.source_event_position {
+        if graph.node(source).is_none() {
+            println!("(caused by position {} which is not part of the log)", source);
+        }
+    }
+
+    for (depth, cause) in causes.iter().enumerate() {
+        print_node(cause, depth, false);
+    }
+
+    let mut visited = HashSet::new();
+    print_effects(&graph, node, causes.len(), &mut visited);
+
+    Ok(())
+}
+
+fn print_effects(graph: &CausalityGraph, node: &Node, depth: usize, visited: &mut HashSet<u64>) {
+    print_node(node, depth, visited.is_empty());
+    if !visited.insert(node.position) {
+        return;
+    }
+
+    for effect in graph.effects(node.position) {
+        print_effects(graph, effect, depth + 1, visited);
+    }
+}
+
+fn print_node(node: &Node, depth: usize, traced: bool) {
+    println!(
+        "{}{} {} {:?} {} (key: {})",
+        "  ".repeat(depth),
+        if traced { "*" } else { "-" },
+        node.position,
+        node.event_type,
+        node.state.unwrap_or(""),
+        node.key
+    );
+}
@@ -0,0 +1,92 @@
+extern crate failure;
+#[macro_use]
+extern crate serde_json;
+extern crate structopt;
+#[macro_use]
+extern crate structopt_derive;
+extern crate zeebe_log_reader;
+
+use failure::Error;
+use std::fs::File;
+use std::io::prelude::*;
+
+use structopt::StructOpt;
+
+use serde_json::Value;
+
+use zeebe_log_reader::LogStream;
+use zeebe_log_reader::state::*;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "zeebe-unfinished-instances",
+            about = "List workflow instances which were created but never completed or canceled")]
+struct Opt {
+    #[structopt(short = "j", long = "json", help = "Output the report as JSON")]
+    json: bool,
+    #[structopt(help = "Input files")]
+    input: Vec<String>,
+}
+
+fn main() {
+    match try_main() {
+        Ok(_) => {}
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+fn try_main() -> Result<(), Error> {
+    let opt = Opt::from_args();
+
+    let mut engine = StateEngine::new();
+
+    for filename in opt.input.iter() {
+        let mut file = File::open(&filename)?;
+
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        let logstream = LogStream::new(&buffer)?;
+
+        for frame in logstream {
+            engine.add(&frame)?;
+        }
+    }
+
+    let state = engine.replay()?;
+    let unfinished = state.unfinished();
+
+    if opt.json {
+        let report: Vec<Value> = unfinished
+            .iter()
+            .map(|(&(ref bpmn_process_id, version), instances)| {
+                json!({
+                    "bpmnProcessId": bpmn_process_id,
+                    "version": version,
+                    "instances": instances,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    if unfinished.is_empty() {
+        println!("All created workflow instances were completed or canceled");
+    }
+
+    for (&(ref bpmn_process_id, version), instances) in &unfinished {
+        println!("{} version {}: {} unfinished", bpmn_process_id, version, instances.len());
+        for instance in instances {
+            println!(
+                "  {} last activity: {}, last position: {}, active activities: {}, open tasks: {}",
+                instance.workflow_instance_key,
+                instance.last_activity.as_ref().map(|a| a.as_str()).unwrap_or("-"),
+                instance.last_position,
+                instance.active_activities.len(),
+                instance.open_tasks.len()
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use data::Frame;
+use failure::Error;
+use msgpack::Event;
+use std::collections::HashMap;
+use EventType;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Node {
+    pub position: u64,
+    pub key: u64,
+    pub source_event_position: Option<u64>,
+    /// The partition of the source event if it is not the partition of the record, such sources are
+    /// not linked in the graph.
+    pub foreign_source_partition: Option<u32>,
+    #[serde(rename = "type")]
+    pub event_type: EventType,
+    pub state: Option<&'static str>,
+    pub command: bool,
+    pub rejection: bool,
+}
+
+impl Node {
+    /// Returns the source event position if the source event is on the same partition.
+    pub fn local_source(&self) -> Option<u64> {
+        match self.foreign_source_partition {
+            Some(_) => None,
+            None => self.source_event_position,
+        }
+    }
+}
+
+/// Links every record to the record it was caused by through `source_event_position`, and back
+/// to everything it caused in turn. Records are expected to be from a single partition, source
+/// events on other partitions are not linked.
+#[derive(Debug, Default)]
+pub struct CausalityGraph {
+    nodes: HashMap<u64, Node>,
+    effects: HashMap<u64, Vec<u64>>,
+}
+
+impl CausalityGraph {
+    pub fn new() -> Self {
+        CausalityGraph::default()
+    }
+
+    pub fn add(&mut self, frame: &Frame) -> Result<(), Error> {
+        let event_type: EventType = frame.entry.metadata.event_type.into();
+        let event = Event::decode(&event_type, frame.entry.event)?;
+        let log_entry = frame.entry.log_entry;
+        let source_event_position = log_entry.source_event_position();
+        let source_partition = log_entry.source_event_stream_partition;
+
+        let node = Node {
+            position: log_entry.position,
+            key: log_entry.key,
+            source_event_position,
+            foreign_source_partition: if source_event_position.is_some() &&
+                source_partition != { frame.data_frame.stream_id }
+            {
+                Some(source_partition)
+            } else {
+                None
+            },
+            event_type,
+            state: event.as_ref().and_then(|e| e.state()),
+            command: event.as_ref().map(|e| e.is_command()).unwrap_or(false),
+            rejection: event.as_ref().map(|e| e.is_rejection()).unwrap_or(false),
+        };
+
+        if let Some(source) = node.local_source() {
+            self.effects.entry(source).or_insert_with(Vec::new).push(node.position);
+        }
+        self.nodes.insert(node.position, node);
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, position: u64) -> Option<&Node> {
+        self.nodes.get(&position)
+    }
+
+    pub fn nodes<'a>(&'a self) -> Box<Iterator<Item = &'a Node> + 'a> {
+        Box::new(self.nodes.values())
+    }
+
+    /// Returns the record which caused the record at the given position.
+    pub fn cause(&self, position: u64) -> Option<&Node> {
+        self.node(position)
+            .and_then(|n| n.local_source())
+            .and_then(|p| self.node(p))
+    }
+
+    /// Returns the chain of causes of the record at the given position, starting with the direct
+    /// cause and ending with the record which started the chain.
+    pub fn causes(&self, position: u64) -> Vec<&Node> {
+        let mut causes: Vec<&Node> = Vec::new();
+        let mut current = position;
+        while let Some(cause) = self.cause(current) {
+            if cause.position == position || causes.iter().any(|c| c.position == cause.position) {
+                break;
+            }
+            causes.push(cause);
+            current = cause.position;
+        }
+        causes
+    }
+
+    /// Returns the records directly caused by the record at the given position ordered by
+    /// position.
+    pub fn effects(&self, position: u64) -> Vec<&Node> {
+        let mut effects: Vec<&Node> = self.effects
+            .get(&position)
+            .map(|e| e.iter().filter_map(|p| self.node(*p)).collect())
+            .unwrap_or_else(Vec::new);
+        effects.sort_by_key(|n| n.position);
+        effects
+    }
+
+    /// Returns the root of the causality chain the record at the given position belongs to.
+    pub fn root(&self, position: u64) -> Option<&Node> {
+        self.causes(position).pop().or_else(|| self.node(position))
+    }
+}
@@ -0,0 +1,350 @@
+use data::*;
+use decode::Decoder;
+use failure::Error;
+use msgpack::{self, Event};
+use std::mem;
+use EventType;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Violation {
+    /// Offset of the frame, or of the header field, in the segment.
+    pub offset: usize,
+    pub position: Option<u64>,
+    pub message: String,
+    /// Structural violations break the framing, so the frame and everything after it can't be
+    /// trusted. Other violations only concern the content of a frame, like its position or event.
+    pub structural: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentCheck {
+    pub frames: usize,
+    /// Offset after the last frame which was structurally valid, including all frames before it.
+    pub valid_end: usize,
+    /// Position of the last frame which was structurally valid, including all frames before it.
+    pub valid_position: Option<u64>,
+    /// Number of frames up to `valid_end`.
+    pub valid_frames: usize,
+    pub violations: Vec<Violation>,
+}
+
+impl SegmentCheck {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    fn violation(&mut self, offset: usize, position: Option<u64>, message: String) {
+        self.violations.push(Violation {
+            offset,
+            position,
+            message,
+            structural: true,
+        });
+    }
+
+    fn content_violation(&mut self, offset: usize, position: Option<u64>, message: String) {
+        self.violations.push(Violation {
+            offset,
+            position,
+            message,
+            structural: false,
+        });
+    }
+
+    fn structural_violations(&self) -> usize {
+        self.violations.iter().filter(|v| v.structural).count()
+    }
+}
+
+/// Validates a segment without stopping at the first problem: the segment header, frame lengths
+/// and padding, SBE headers, position order and event bodies, which have to be a single msgpack
+/// value. Checking only stops if a frame is too broken to find the start of the next frame.
+pub fn check_segment(data: &[u8]) -> SegmentCheck {
+    let mut check = SegmentCheck::default();
+
+    let header_length = mem::size_of::<FsLogSegment>();
+    if data.len() < header_length {
+        let message = format!(
+            "Segment has only {} bytes but the header requires {}",
+            data.len(),
+            header_length
+        );
+        check.violation(0, None, message);
+        return check;
+    }
+
+    let mut decoder = Decoder::new(data);
+    let segment: &FsLogSegment = match decoder.read_type() {
+        Ok(segment) => segment,
+        Err(e) => {
+            check.violation(0, None, e.to_string());
+            return check;
+        }
+    };
+
+    let size = segment.size as usize;
+    let capacity = segment.capacity as usize;
+    if size > capacity {
+        check.violation(0, None, format!("Segment size {} exceeds its capacity {}", size, capacity));
+    }
+    if size > data.len() {
+        check.violation(0, None, format!("Segment size {} exceeds the file length {}", size, data.len()));
+    }
+    if size < BLOCK_SIZE {
+        check.violation(0, None, format!("Segment size {} is smaller than the header block {}", size, BLOCK_SIZE));
+        return check;
+    }
+
+    let end = size.min(data.len());
+    let mut decoder = Decoder::new(&data[..end]);
+    if let Err(e) = decoder.read(BLOCK_SIZE) {
+        check.violation(0, None, e.to_string());
+        return check;
+    }
+
+    check.valid_end = BLOCK_SIZE;
+
+    let mut last_position = None;
+    while !decoder.is_empty() {
+        let offset = decoder.position();
+        let violations = check.structural_violations();
+
+        let length = match check_frame(&mut check, &data[offset..end], offset, &mut last_position) {
+            Some(length) => length,
+            None => break,
+        };
+
+        let padding = offset + length;
+        if let Err(e) = decoder.read(length).and_then(|_| decoder.align(FRAME_ALIGNMENT)) {
+            check.violation(offset, last_position, format!("Frame padding exceeds the segment: {}", e));
+            break;
+        }
+        if data[padding..decoder.position()].iter().any(|&b| b != 0) {
+            let message = format!("Frame padding after the frame length {} is not zeroed", length);
+            check.violation(offset, last_position, message);
+        }
+
+        check.frames += 1;
+        if check.structural_violations() == violations && check.valid_end == offset {
+            check.valid_end = decoder.position();
+            check.valid_position = last_position;
+            check.valid_frames = check.frames;
+        }
+    }
+
+    check
+}
+
+/// Checks a single frame and returns its length, or `None` if the frame is too broken to continue.
+fn check_frame(check: &mut SegmentCheck, data: &[u8], offset: usize, last_position: &mut Option<u64>) -> Option<usize> {
+    let mut decoder = Decoder::new(data);
+    let data_frame: &DataFrame = match decoder.read_type() {
+        Ok(data_frame) => data_frame,
+        Err(e) => {
+            check.violation(offset, None, format!("Incomplete frame header: {}", e));
+            return None;
+        }
+    };
+
+    let length = data_frame.length as usize;
+    if length < mem::size_of::<DataFrame>() {
+        check.violation(offset, None, format!("Frame length {} is smaller than the frame header", length));
+        return None;
+    }
+    if length > data.len() {
+        let message = format!(
+            "Frame length {} exceeds the remaining {} bytes of the segment",
+            length,
+            data.len()
+        );
+        check.violation(offset, None, message);
+        return None;
+    }
+
+    if data_frame.frame_type != FRAME_MESSAGE {
+        return Some(length);
+    }
+
+    let mut decoder = Decoder::new(&data[..length]);
+    if let Err(e) = check_message(&mut decoder, offset, last_position, check) {
+        check.violation(offset, *last_position, e.to_string());
+    }
+
+    Some(length)
+}
+
+fn check_message(
+    decoder: &mut Decoder,
+    offset: usize,
+    last_position: &mut Option<u64>,
+    check: &mut SegmentCheck,
+) -> Result<(), Error> {
+    let data_frame: &DataFrame = decoder.read_type()?;
+    let log_entry: &LogEntry = decoder.read_type()?;
+    let position = log_entry.position;
+
+    if let Some(last) = *last_position {
+        if position <= last {
+            let message = format!(
+                "Position {} is not greater than the previous position {}",
+                position,
+                last
+            );
+            check.content_violation(offset, Some(position), message);
+        }
+    }
+    *last_position = Some(position);
+
+    let metadata_length = log_entry.metadata_length as usize;
+    let header_length = mem::size_of::<DataFrame>() + mem::size_of::<LogEntry>();
+    if header_length + metadata_length > data_frame.length as usize {
+        bail!("Metadata length {} exceeds the frame length {}", metadata_length, { data_frame.length });
+    }
+
+    let expected_metadata_length = mem::size_of::<SbeHeader>() + mem::size_of::<Metadata>();
+    if metadata_length != expected_metadata_length {
+        bail!("Metadata length {} does not match the expected length {}", metadata_length, expected_metadata_length);
+    }
+
+    let sbe_header: &SbeHeader = decoder.read_type()?;
+    if sbe_header != &Metadata::sbe_header() {
+        bail!("Unexpected SBE header {:?}", sbe_header);
+    }
+
+    let metadata: &Metadata = decoder.read_type()?;
+    let event = decoder.read(data_frame.length as usize - header_length - metadata_length)?;
+
+    if let Err(e) = check_event(metadata.event_type.into(), event) {
+        check.content_violation(offset, Some(position), e.to_string());
+    }
+
+    Ok(())
+}
+
+fn check_event(event_type: EventType, event: &[u8]) -> Result<(), Error> {
+    // noop events have an empty body
+    if !event.is_empty() {
+        if let Err(e) = msgpack::decode_value(event) {
+            bail!("Invalid msgpack body of {:?} event: {}", event_type, e);
+        }
+    }
+    if let Err(e) = Event::decode(&event_type, event) {
+        bail!("Unable to deserialize {:?} event: {}", event_type, e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encode::SegmentWriter;
+    use std::fs::File;
+    use std::io::Read;
+
+    const FIRST_POSITION: u64 = 1 << 32;
+    /// Length of a noop frame including its padding.
+    const NOOP_FRAME: usize = 96;
+
+    fn noop_segment(positions: &[u64]) -> Vec<u8> {
+        let mut writer = SegmentWriter::new(0, 0, 1 << 16);
+        for &position in positions {
+            writer.append(&LogEntry::new(position, 0), &Metadata::new(8), &[]).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn write_le(data: &mut [u8], offset: usize, bytes: usize, value: u64) {
+        for i in 0..bytes {
+            data[offset + i] = (value >> (i * 8)) as u8;
+        }
+    }
+
+    fn assert_broken_at(check: &SegmentCheck, offset: usize, message: &str) {
+        assert_eq!(check.violations.len(), 1, "{:?}", check.violations);
+        assert_eq!(check.violations[0].offset, offset);
+        assert!(check.violations[0].structural);
+        assert!(check.violations[0].message.contains(message), "{}", check.violations[0].message);
+        assert_eq!(check.valid_end, offset);
+    }
+
+    #[test]
+    fn sample_segment_is_valid() {
+        let mut data = Vec::new();
+        File::open(concat!(env!("CARGO_MANIFEST_DIR"), "/log/00.data"))
+            .and_then(|mut f| f.read_to_end(&mut data))
+            .unwrap();
+        let size = fs_log_segment(&data).unwrap().size as usize;
+
+        let check = check_segment(&data);
+        assert!(check.is_valid(), "{:?}", check.violations);
+        assert_eq!(check.frames, 135);
+        assert_eq!(check.valid_frames, 135);
+        assert_eq!(check.valid_end, size);
+    }
+
+    #[test]
+    fn corrupted_sbe_header_is_structural() {
+        let mut data = noop_segment(&[FIRST_POSITION, FIRST_POSITION + 1, FIRST_POSITION + 2]);
+        let offset = BLOCK_SIZE + NOOP_FRAME;
+        let sbe_header = offset + mem::size_of::<DataFrame>() + mem::size_of::<LogEntry>();
+        data[sbe_header + 2] ^= 0xff;
+
+        let check = check_segment(&data);
+        assert_broken_at(&check, offset, "Unexpected SBE header");
+        assert_eq!(check.frames, 3);
+        assert_eq!(check.valid_frames, 1);
+        assert_eq!(check.valid_position, Some(FIRST_POSITION));
+    }
+
+    #[test]
+    fn oversized_frame_length_stops_the_check() {
+        let mut data = noop_segment(&[FIRST_POSITION, FIRST_POSITION + 1, FIRST_POSITION + 2]);
+        let offset = BLOCK_SIZE + NOOP_FRAME;
+        write_le(&mut data, offset, 4, 1 << 20);
+
+        let check = check_segment(&data);
+        assert_broken_at(&check, offset, "exceeds the remaining");
+        assert_eq!(check.frames, 1);
+        assert_eq!(check.valid_frames, 1);
+    }
+
+    #[test]
+    fn metadata_length_mismatch_is_structural() {
+        let mut data = noop_segment(&[FIRST_POSITION, FIRST_POSITION + 1]);
+        let offset = BLOCK_SIZE + NOOP_FRAME;
+        write_le(&mut data, offset + mem::size_of::<DataFrame>() + 40, 2, 38);
+
+        let check = check_segment(&data);
+        assert_broken_at(&check, offset, "does not match the expected length");
+        assert_eq!(check.frames, 2);
+        assert_eq!(check.valid_frames, 1);
+    }
+
+    #[test]
+    fn dirty_padding_is_structural() {
+        let mut data = noop_segment(&[FIRST_POSITION, FIRST_POSITION + 1]);
+        data[BLOCK_SIZE + NOOP_FRAME - 1] = 1;
+
+        let check = check_segment(&data);
+        assert_broken_at(&check, BLOCK_SIZE, "not zeroed");
+        assert_eq!(check.valid_frames, 0);
+    }
+
+    #[test]
+    fn position_regression_only_concerns_the_content() {
+        let positions = [FIRST_POSITION, FIRST_POSITION + 2, FIRST_POSITION + 1];
+        let data = noop_segment(&positions);
+
+        let check = check_segment(&data);
+        assert_eq!(check.violations.len(), 1, "{:?}", check.violations);
+        let violation = &check.violations[0];
+        assert_eq!(violation.offset, BLOCK_SIZE + 2 * NOOP_FRAME);
+        assert_eq!(violation.position, Some(FIRST_POSITION + 1));
+        assert!(!violation.structural);
+        assert_eq!(check.valid_end, data.len());
+        assert_eq!(check.valid_frames, 3);
+        assert_eq!(check.valid_position, Some(FIRST_POSITION + 1));
+    }
+}
@@ -1,9 +1,10 @@
 use decode::Decoder;
 use failure::Error;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
 use std::{self, fmt, mem};
 
 const CACHE_LINE_LENGTH: usize = 64;
-const BLOCK_SIZE: usize = 4 * 1024;
+pub const BLOCK_SIZE: usize = 4 * 1024;
 pub const FRAME_ALIGNMENT: usize = 8;
 pub const FRAME_MESSAGE: u16 = 0;
 
@@ -29,6 +30,17 @@ impl fmt::Debug for FsLogSegment {
     }
 }
 
+impl Serialize for FsLogSegment {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("FsLogSegment", 4)?;
+        s.serialize_field("id", &{ self.id })?;
+        s.serialize_field("version", &{ self.version })?;
+        s.serialize_field("capacity", &{ self.capacity })?;
+        s.serialize_field("size", &{ self.size })?;
+        s.end()
+    }
+}
+
 #[derive(Debug)]
 #[repr(C, packed)]
 pub struct DataFrame {
@@ -39,6 +51,18 @@ pub struct DataFrame {
     pub stream_id: u32,
 }
 
+impl Serialize for DataFrame {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("DataFrame", 5)?;
+        s.serialize_field("length", &{ self.length })?;
+        s.serialize_field("version", &{ self.version })?;
+        s.serialize_field("flags", &{ self.flags })?;
+        s.serialize_field("type", &{ self.frame_type })?;
+        s.serialize_field("streamId", &{ self.stream_id })?;
+        s.end()
+    }
+}
+
 #[repr(C, packed)]
 pub struct LogEntry {
     pub version: u16,
@@ -71,7 +95,40 @@ impl fmt::Debug for LogEntry {
     }
 }
 
+impl Serialize for LogEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("LogEntry", 8)?;
+        s.serialize_field("version", &{ self.version })?;
+        s.serialize_field("position", &{ self.position })?;
+        s.serialize_field("raftTerm", &{ self.raft_term })?;
+        s.serialize_field("producer", &non_null(self.producer, std::u32::MAX))?;
+        s.serialize_field(
+            "sourceEventStreamPartition",
+            &non_null(self.source_event_stream_partition, std::u32::MAX),
+        )?;
+        s.serialize_field("sourceEventPosition", &self.source_event_position())?;
+        s.serialize_field("key", &{ self.key })?;
+        s.serialize_field("metadataLength", &{ self.metadata_length })?;
+        s.end()
+    }
+}
+
 impl LogEntry {
+    pub fn new(position: u64, key: u64) -> Self {
+        LogEntry {
+            version: 0,
+            _reserved: 0,
+            position,
+            raft_term: 0,
+            producer: std::u32::MAX,
+            source_event_stream_partition: std::u32::MAX,
+            source_event_position: std::u64::MAX,
+            key,
+            metadata_length: (mem::size_of::<SbeHeader>() + mem::size_of::<Metadata>()) as u16,
+            _unused: 0,
+        }
+    }
+
     pub fn source_event_position(&self) -> Option<u64> {
         if self.source_event_position < std::u64::MAX {
             Some(self.source_event_position)
@@ -81,6 +138,16 @@ impl LogEntry {
     }
 }
 
+/// Maps the SBE null value of a field to `None`, so it is serialized as `null` instead of a sentinel
+/// which JSON readers can't represent exactly.
+fn non_null<T: PartialEq>(value: T, null: T) -> Option<T> {
+    if value == null {
+        None
+    } else {
+        Some(value)
+    }
+}
+
 #[derive(Debug, PartialEq)]
 #[repr(C, packed)]
 pub struct SbeHeader {
@@ -90,6 +157,17 @@ pub struct SbeHeader {
     pub version: u16,
 }
 
+impl Serialize for SbeHeader {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("SbeHeader", 4)?;
+        s.serialize_field("blockLength", &{ self.block_length })?;
+        s.serialize_field("templateId", &{ self.template_id })?;
+        s.serialize_field("schemaId", &{ self.schema_id })?;
+        s.serialize_field("version", &{ self.version })?;
+        s.end()
+    }
+}
+
 
 #[derive(Debug)]
 #[repr(C, packed)]
@@ -102,7 +180,31 @@ pub struct Metadata {
     pub incident_key: u64,
 }
 
+impl Serialize for Metadata {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Metadata", 6)?;
+        s.serialize_field("requestStreamId", &non_null(self.request_stream_id, std::i32::MIN))?;
+        s.serialize_field("requestId", &non_null(self.request_id, std::u64::MAX))?;
+        s.serialize_field("subscriptionId", &non_null(self.subscription_id, std::u64::MAX))?;
+        s.serialize_field("protocolVersion", &{ self.protocol_version })?;
+        s.serialize_field("eventType", &{ self.event_type })?;
+        s.serialize_field("incidentKey", &non_null(self.incident_key, std::u64::MAX))?;
+        s.end()
+    }
+}
+
 impl Metadata {
+    pub fn new(event_type: u8) -> Self {
+        Metadata {
+            request_stream_id: std::i32::MIN,
+            request_id: std::u64::MAX,
+            subscription_id: std::u64::MAX,
+            protocol_version: 1,
+            event_type,
+            incident_key: std::u64::MAX,
+        }
+    }
+
     pub fn sbe_header() -> SbeHeader {
         SbeHeader {
             block_length: mem::size_of::<Metadata>() as u16,
@@ -124,6 +226,19 @@ pub struct Frame<'d> {
     pub entry: Entry<'d>,
 }
 
+pub fn fs_log_segment(data: &[u8]) -> Result<&FsLogSegment, Error> {
+    Decoder::new(data).read_type()
+}
+
+pub fn set_fs_log_segment_size(data: &mut [u8], size: u32) -> Result<(), Error> {
+    if data.len() < mem::size_of::<FsLogSegment>() {
+        bail!("Not enough bytes for the segment header: only {} bytes available", data.len());
+    }
+    let segment = unsafe { &mut *(data.as_mut_ptr() as *mut FsLogSegment) };
+    segment.size = size;
+    Ok(())
+}
+
 pub fn decode_fs_log_segment<'d>(decoder: &'d mut Decoder) -> Result<&'d FsLogSegment, Error> {
     let segment: &FsLogSegment = decoder.read_type()?;
     decoder.truncate(segment.size as usize)?;
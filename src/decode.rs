@@ -1,7 +1,7 @@
 use failure::Error;
 use std::{fmt, mem};
 
-fn align(value: usize, alignment: usize) -> usize {
+pub fn align(value: usize, alignment: usize) -> usize {
     (value + (alignment - 1)) & !(alignment - 1)
 }
 
@@ -80,6 +80,11 @@ impl<'d> Decoder<'d> {
         Ok(())
     }
 
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.position >= self.data.len()
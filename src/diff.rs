@@ -0,0 +1,187 @@
+use data::Frame;
+use failure::Error;
+use msgpack;
+use serde_json;
+use std::collections::BTreeSet;
+use std::iter::Peekable;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Divergence {
+    OnlyLeft,
+    OnlyRight,
+    /// The frame exists in both logs, but these parts differ.
+    Changed(Vec<&'static str>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Difference {
+    pub position: u64,
+    pub divergence: Divergence,
+}
+
+/// Consecutive frames which differ, frames only present in one log count as differing.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffRange {
+    pub first_position: u64,
+    pub last_position: u64,
+    pub frames: usize,
+    pub only_left: usize,
+    pub only_right: usize,
+    /// The differing parts of frames present in both logs.
+    pub changed: BTreeSet<&'static str>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogDiff {
+    pub left_frames: usize,
+    pub right_frames: usize,
+    pub equal_frames: usize,
+    pub first_divergence: Option<Difference>,
+    pub ranges: Vec<DiffRange>,
+    #[serde(skip)]
+    in_range: bool,
+}
+
+impl LogDiff {
+    pub fn is_equal(&self) -> bool {
+        self.first_divergence.is_none()
+    }
+
+    fn equal(&mut self) {
+        self.equal_frames += 1;
+        self.in_range = false;
+    }
+
+    fn difference(&mut self, difference: Difference) {
+        if !self.in_range {
+            self.ranges.push(DiffRange {
+                first_position: difference.position,
+                last_position: difference.position,
+                frames: 0,
+                only_left: 0,
+                only_right: 0,
+                changed: BTreeSet::new(),
+            });
+            self.in_range = true;
+        }
+
+        if let Some(range) = self.ranges.last_mut() {
+            range.last_position = difference.position;
+            range.frames += 1;
+            match difference.divergence {
+                Divergence::OnlyLeft => range.only_left += 1,
+                Divergence::OnlyRight => range.only_right += 1,
+                Divergence::Changed(ref parts) => range.changed.extend(parts.iter().cloned()),
+            }
+        }
+
+        if self.first_divergence.is_none() {
+            self.first_divergence = Some(difference);
+        }
+    }
+}
+
+/// Compares two logs frame by frame, frames are matched by their position. Both logs have to be
+/// ordered by position, as the frames of a partition are.
+pub fn diff<'l, 'r, L, R>(left: L, right: R) -> Result<LogDiff, Error>
+where
+    L: Iterator<Item = Frame<'l>>,
+    R: Iterator<Item = Frame<'r>>,
+{
+    let mut left: Peekable<L> = left.peekable();
+    let mut right: Peekable<R> = right.peekable();
+    let mut diff = LogDiff::default();
+
+    loop {
+        let left_position = left.peek().map(|f| f.entry.log_entry.position);
+        let right_position = right.peek().map(|f| f.entry.log_entry.position);
+
+        match (left_position, right_position) {
+            (None, None) => break,
+            (Some(l), Some(r)) if l == r => {
+                let (l, r) = match (left.next(), right.next()) {
+                    (Some(l), Some(r)) => (l, r),
+                    _ => unreachable!(),
+                };
+                diff.left_frames += 1;
+                diff.right_frames += 1;
+
+                let parts = compare(&l, &r)?;
+                if parts.is_empty() {
+                    diff.equal();
+                } else {
+                    diff.difference(Difference {
+                        position: l.entry.log_entry.position,
+                        divergence: Divergence::Changed(parts),
+                    });
+                }
+            }
+            (Some(l), r) if r.map_or(true, |r| l < r) => {
+                left.next();
+                diff.left_frames += 1;
+                diff.difference(Difference {
+                    position: l,
+                    divergence: Divergence::OnlyLeft,
+                });
+            }
+            (_, Some(r)) => {
+                right.next();
+                diff.right_frames += 1;
+                diff.difference(Difference {
+                    position: r,
+                    divergence: Divergence::OnlyRight,
+                });
+            }
+            (Some(_), None) => unreachable!(),
+        }
+    }
+
+    Ok(diff)
+}
+
+/// Returns the parts in which two frames with the same position differ.
+pub fn compare(left: &Frame, right: &Frame) -> Result<Vec<&'static str>, Error> {
+    let mut parts = Vec::new();
+    let (l, r) = (left.entry.log_entry, right.entry.log_entry);
+
+    if { l.raft_term } != { r.raft_term } {
+        parts.push("raftTerm");
+    }
+    if { l.key } != { r.key } {
+        parts.push("key");
+    }
+    if { l.source_event_position } != { r.source_event_position } ||
+        { l.source_event_stream_partition } != { r.source_event_stream_partition }
+    {
+        parts.push("sourceEvent");
+    }
+    if { l.producer } != { r.producer } {
+        parts.push("producer");
+    }
+    if serde_json::to_value(left.entry.metadata)? != serde_json::to_value(right.entry.metadata)? {
+        parts.push("metadata");
+    }
+    if !same_event(left, right) {
+        parts.push("event");
+    }
+
+    Ok(parts)
+}
+
+/// Compares the decoded msgpack documents of the events, so that a different msgpack encoding of
+/// the same document is not reported. Events which fail to decode are compared byte by byte.
+fn same_event(left: &Frame, right: &Frame) -> bool {
+    if left.entry.metadata.event_type != right.entry.metadata.event_type {
+        return false;
+    }
+    match (
+        msgpack::decode_value(left.entry.event),
+        msgpack::decode_value(right.entry.event),
+    ) {
+        (Ok(l), Ok(r)) => l == r,
+        _ => left.entry.event == right.entry.event,
+    }
+}
@@ -0,0 +1,221 @@
+use data::*;
+use decode::align;
+use failure::Error;
+use msgpack;
+use serde::Serialize;
+use std::{mem, slice};
+
+fn bytes<T>(value: &T) -> &[u8] {
+    unsafe { slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()) }
+}
+
+/// Writes a log segment in the format read by `LogStream`: the `FsLogSegment` header padded to
+/// `BLOCK_SIZE`, followed by message frames aligned to `FRAME_ALIGNMENT`. Appending fails if a
+/// frame doesn't fit into the capacity of the segment.
+///
+/// Reading the result with `LogStream` yields the appended log entries, metadata and event bytes
+/// unchanged and in order. Copying all frames of a segment with `append_frame` into a writer
+/// created by `from_segment` reproduces a segment which only contains message frames byte for
+/// byte up to its size.
+pub struct SegmentWriter {
+    buffer: Vec<u8>,
+    stream_id: u32,
+}
+
+impl SegmentWriter {
+    pub fn new(id: u32, version: u16, capacity: u32) -> Self {
+        let mut buffer = vec![0; BLOCK_SIZE];
+        {
+            let segment = unsafe { &mut *(buffer.as_mut_ptr() as *mut FsLogSegment) };
+            segment.id = id;
+            segment.version = version;
+            segment.capacity = capacity;
+        }
+        SegmentWriter { buffer, stream_id: 0 }
+    }
+
+    /// Creates a writer with the id, version and capacity of an existing segment.
+    pub fn from_segment(data: &[u8]) -> Result<Self, Error> {
+        let segment = fs_log_segment(data)?;
+        Ok(SegmentWriter::new(segment.id, segment.version, segment.capacity))
+    }
+
+    /// Sets the stream id written to the data frames of appended entries, `append_frame` keeps the
+    /// stream id of the copied frame.
+    pub fn set_stream_id(&mut self, stream_id: u32) {
+        self.stream_id = stream_id;
+    }
+
+    /// Returns the size of the segment written so far, including the header block.
+    pub fn size(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns `true` if no frames were appended yet.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.len() <= BLOCK_SIZE
+    }
+
+    pub fn append(&mut self, log_entry: &LogEntry, metadata: &Metadata, event: &[u8]) -> Result<(), Error> {
+        let stream_id = self.stream_id;
+        self.write_frame(0, 0, stream_id, log_entry, metadata, event)
+    }
+
+    /// Appends an event encoded with `msgpack::serialize`. It decodes to an equal event, but the
+    /// bytes can differ from the encoding written by the broker.
+    pub fn append_event<S: Serialize>(
+        &mut self,
+        log_entry: &LogEntry,
+        metadata: &Metadata,
+        event: &S,
+    ) -> Result<(), Error> {
+        let event = msgpack::serialize(event)?;
+        self.append(log_entry, metadata, &event)
+    }
+
+    pub fn append_frame(&mut self, frame: &Frame) -> Result<(), Error> {
+        self.append_frame_with_event(frame, frame.entry.event)
+    }
+
+    /// Appends a copy of the frame with a different event body.
+    pub fn append_frame_with_event(&mut self, frame: &Frame, event: &[u8]) -> Result<(), Error> {
+        let data_frame = frame.data_frame;
+        self.write_frame(
+            data_frame.version,
+            data_frame.flags,
+            data_frame.stream_id,
+            frame.entry.log_entry,
+            frame.entry.metadata,
+            event,
+        )
+    }
+
+    fn write_frame(
+        &mut self,
+        version: u8,
+        flags: u8,
+        stream_id: u32,
+        log_entry: &LogEntry,
+        metadata: &Metadata,
+        event: &[u8],
+    ) -> Result<(), Error> {
+        let metadata_length = mem::size_of::<SbeHeader>() + mem::size_of::<Metadata>();
+        if log_entry.metadata_length as usize != metadata_length {
+            bail!(
+                "Unsupported metadata length {} of log entry at position {}, expected {}",
+                { log_entry.metadata_length },
+                { log_entry.position },
+                metadata_length
+            );
+        }
+
+        let length = mem::size_of::<DataFrame>() + mem::size_of::<LogEntry>() + metadata_length + event.len();
+        let capacity = fs_log_segment(&self.buffer)?.capacity as usize;
+        if self.buffer.len() + align(length, FRAME_ALIGNMENT) > capacity {
+            bail!(
+                "Log entry at position {} exceeds the segment capacity of {} bytes",
+                { log_entry.position },
+                capacity
+            );
+        }
+
+        let data_frame = DataFrame {
+            length: length as u32,
+            version,
+            flags,
+            frame_type: FRAME_MESSAGE,
+            stream_id,
+        };
+
+        self.buffer.extend_from_slice(bytes(&data_frame));
+        self.buffer.extend_from_slice(bytes(log_entry));
+        self.buffer.extend_from_slice(bytes(&Metadata::sbe_header()));
+        self.buffer.extend_from_slice(bytes(metadata));
+        self.buffer.extend_from_slice(event);
+
+        let end = align(self.buffer.len(), FRAME_ALIGNMENT);
+        self.buffer.resize(end, 0);
+
+        Ok(())
+    }
+
+    /// Returns the segment with the size in its header set to the number of written bytes.
+    pub fn finish(self) -> Result<Vec<u8>, Error> {
+        let mut buffer = self.buffer;
+        let size = buffer.len() as u32;
+        set_fs_log_segment_size(&mut buffer, size)?;
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use msgpack::Event;
+    use std::fs::File;
+    use std::io::Read;
+    use LogStream;
+
+    fn segment() -> Vec<u8> {
+        let mut data = Vec::new();
+        File::open(concat!(env!("CARGO_MANIFEST_DIR"), "/log/00.data"))
+            .and_then(|mut f| f.read_to_end(&mut data))
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn copied_frames_reproduce_the_segment() {
+        let data = segment();
+        let size = fs_log_segment(&data).unwrap().size as usize;
+
+        let mut writer = SegmentWriter::from_segment(&data).unwrap();
+        for frame in LogStream::new(&data).unwrap() {
+            writer.append_frame(&frame).unwrap();
+        }
+        assert_eq!(writer.size(), size);
+
+        let copy = writer.finish().unwrap();
+        assert_eq!(&copy[..], &data[..size]);
+    }
+
+    #[test]
+    fn appended_events_decode_to_equal_events() {
+        let data = segment();
+
+        let mut writer = SegmentWriter::new(0, 0, 1 << 20);
+        writer.set_stream_id(1);
+        let mut events = Vec::new();
+        for frame in LogStream::new(&data).unwrap() {
+            let event_type = frame.entry.metadata.event_type.into();
+            if let Some(event) = Event::decode(&event_type, frame.entry.event).unwrap() {
+                writer.append_event(frame.entry.log_entry, frame.entry.metadata, &event).unwrap();
+                events.push(event);
+            }
+        }
+        assert!(!events.is_empty());
+
+        let copy = writer.finish().unwrap();
+        let decoded: Vec<Event> = LogStream::new(&copy)
+            .unwrap()
+            .map(|frame| {
+                Event::decode(&frame.entry.metadata.event_type.into(), frame.entry.event)
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(decoded, events);
+    }
+
+    #[test]
+    fn appending_beyond_the_capacity_fails() {
+        let mut writer = SegmentWriter::new(0, 0, BLOCK_SIZE as u32 + 256);
+        let log_entry = LogEntry::new(1 << 32, 0);
+        let metadata = Metadata::new(8);
+
+        writer.append(&log_entry, &metadata, &[0; 128]).unwrap();
+        let size = writer.size();
+        assert!(writer.append(&log_entry, &metadata, &[0; 128]).is_err());
+        assert_eq!(writer.size(), size);
+    }
+}
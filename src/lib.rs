@@ -1,15 +1,36 @@
 #[macro_use]
 extern crate failure;
+extern crate regex;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_bytes;
+extern crate serde_json;
 extern crate rmp_serde;
+extern crate rmpv;
 
-mod data;
+pub mod anomalies;
+pub mod causality;
+pub mod check;
+pub mod data;
 mod decode;
+pub mod diff;
+pub mod encode;
 pub mod msgpack;
+pub mod orphans;
 pub mod output;
+pub mod query;
+pub mod record;
+pub mod redact;
+pub mod requests;
+pub mod rejections;
+pub mod repair;
+pub mod slice;
+pub mod state;
+pub mod stats;
+pub mod tasks;
+pub mod terms;
+pub mod timeline;
 
 use data::*;
 use decode::Decoder;
@@ -17,7 +38,7 @@ use decode::Decoder;
 use failure::Error;
 use std::{convert, iter, mem};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum EventType {
     Task,
     Raft,
@@ -80,14 +101,23 @@ impl<'d> LogStream<'d> {
                     let log_entry: &LogEntry = self.decoder.read_type()?;
 
                     let sbe_header: &SbeHeader = self.decoder.read_type()?;
-                    assert_eq!(&Metadata::sbe_header(), sbe_header);
+                    if sbe_header != &Metadata::sbe_header() {
+                        bail!("Unexpected SBE header {:?} at position {}", sbe_header, { log_entry.position });
+                    }
 
                     let metadata: &Metadata = self.decoder.read_type()?;
 
-                    let event = self.decoder.read(
-                        data_frame.length as usize - mem::size_of_val(data_frame) - mem::size_of_val(log_entry) -
-                            log_entry.metadata_length as usize,
-                    )?;
+                    let header_length =
+                        mem::size_of_val(data_frame) + mem::size_of_val(log_entry) + log_entry.metadata_length as usize;
+                    let event_length = match (data_frame.length as usize).checked_sub(header_length) {
+                        Some(event_length) => event_length,
+                        None => bail!(
+                            "Frame length {} at position {} is smaller than its headers",
+                            { data_frame.length },
+                            { log_entry.position }
+                        ),
+                    };
+                    let event = self.decoder.read(event_length)?;
 
                     Some(Entry {
                         log_entry,
@@ -110,8 +140,8 @@ impl<'d> LogStream<'d> {
     }
 }
 
-#[derive(Debug)]
-enum Producer {
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Producer {
     TaskQueue,
     TaskLock,
     TaskExpireLock,
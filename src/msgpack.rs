@@ -2,10 +2,12 @@
 
 use failure::Error;
 use rmp_serde::Deserializer;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
+use serde_json::{Number, Value};
 use std::collections::HashMap;
 use std::fmt;
+use EventType;
 
 macro_rules! enum_serialize {
     ($e:ty => {$( $t:path => $s:expr ),* }) => {
@@ -39,12 +41,31 @@ impl<'d> ::serde::Deserialize<'d> for $e {
     }
 }
 
+impl ::serde::Serialize for $e {
+
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl $e {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            $($t => $s,) *
+        }
+    }
+}
+
 };
 }
 
-#[derive(PartialEq, Deserialize)]
+#[derive(PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WorkflowEvent {
+    pub state: WorkflowState,
     pub bpmn_process_id: String,
     pub version: i32,
     pub bpmn_xml: ByteBuf,
@@ -54,6 +75,7 @@ pub struct WorkflowEvent {
 impl fmt::Debug for WorkflowEvent {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("WorkflowEvent")
+            .field("state", &self.state)
             .field("bpmn_process_id", &self.bpmn_process_id)
             .field("version", &self.version)
             .field("deployment_key", &self.deployment_key)
@@ -61,7 +83,20 @@ impl fmt::Debug for WorkflowEvent {
     }
 }
 
-#[derive(PartialEq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Eq)]
+pub enum WorkflowState {
+    Create,
+    Created,
+}
+
+enum_serialize!{
+    WorkflowState => {
+        WorkflowState::Create => "CREATE",
+        WorkflowState::Created => "CREATED"
+    }
+}
+
+#[derive(PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WorkflowInstanceEvent {
     pub state: WorkflowInstanceState,
@@ -86,7 +121,13 @@ impl fmt::Debug for WorkflowInstanceEvent {
     }
 }
 
-#[derive(Debug, PartialEq, Hash, Eq)]
+impl WorkflowInstanceEvent {
+    pub fn payload_json(&self) -> Result<Value, Error> {
+        decode_payload(&self.payload)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Eq)]
 pub enum WorkflowInstanceState {
     CreateWorkflowInstance,
     WorkflowInstanceCreated,
@@ -147,12 +188,28 @@ enum_serialize!{
     }
 }
 
+impl WorkflowInstanceState {
+    pub fn is_command(&self) -> bool {
+        match *self {
+            WorkflowInstanceState::CreateWorkflowInstance |
+            WorkflowInstanceState::CancelWorkflowInstance |
+            WorkflowInstanceState::UpdatePayload => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_rejection(&self) -> bool {
+        self.as_str().ends_with("_REJECTED")
+    }
+}
 
-#[derive(PartialEq, Deserialize)]
+
+#[derive(PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TaskEvent {
     pub state: TaskState,
     pub lock_time: i64,
+    pub lock_owner: String,
     pub retries: i32,
     #[serde(rename = "type")]
     pub task_type: String,
@@ -166,6 +223,7 @@ impl fmt::Debug for TaskEvent {
         f.debug_struct("TaskEvent")
             .field("state", &self.state)
             .field("lock_time", &self.lock_time)
+            .field("lock_owner", &self.lock_owner)
             .field("retries", &self.retries)
             .field("type", &self.task_type)
             .field("headers", &self.headers)
@@ -174,7 +232,13 @@ impl fmt::Debug for TaskEvent {
     }
 }
 
-#[derive(Debug, PartialEq, Hash, Eq)]
+impl TaskEvent {
+    pub fn payload_json(&self) -> Result<Value, Error> {
+        decode_payload(&self.payload)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Eq)]
 pub enum TaskState {
     Create,
     Created,
@@ -235,15 +299,117 @@ enum_serialize!{
     }
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
+impl TaskState {
+    pub fn is_command(&self) -> bool {
+        match *self {
+            TaskState::Create |
+            TaskState::Lock |
+            TaskState::Complete |
+            TaskState::ExpireLock |
+            TaskState::Fail |
+            TaskState::UpdateRetries |
+            TaskState::Cancel => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_rejection(&self) -> bool {
+        self.as_str().ends_with("_REJECTED")
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TaskHeaders {
-    workflow_instance_key: i64,
-    bpmn_process_id: String,
-    workflow_definition_version: i32,
-    workflow_key: i64,
-    activity_id: String,
-    activity_instance_key: i64,
+    pub workflow_instance_key: i64,
+    pub bpmn_process_id: String,
+    pub workflow_definition_version: i32,
+    pub workflow_key: i64,
+    pub activity_id: String,
+    pub activity_instance_key: i64,
+}
+
+/// The raft configuration event a leader writes when it starts a term. It lists the members of the
+/// cluster, but not which of them is the leader.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RaftEvent {
+    #[serde(default)]
+    pub members: Vec<RaftMember>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RaftMember {
+    #[serde(default)]
+    pub host: String,
+    #[serde(default)]
+    pub port: i32,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Event {
+    Task(TaskEvent),
+    Workflow(WorkflowEvent),
+    WorkflowInstance(WorkflowInstanceEvent),
+}
+
+impl Event {
+    pub fn decode(event_type: &EventType, data: &[u8]) -> Result<Option<Event>, Error> {
+        let event = match *event_type {
+            EventType::Task => Some(Event::Task(deserialize(data)?)),
+            EventType::Workflow => Some(Event::Workflow(deserialize(data)?)),
+            EventType::WorkflowInstance => Some(Event::WorkflowInstance(deserialize(data)?)),
+            _ => None,
+        };
+        Ok(event)
+    }
+
+    pub fn state(&self) -> Option<&'static str> {
+        match *self {
+            Event::Task(ref e) => Some(e.state.as_str()),
+            Event::WorkflowInstance(ref e) => Some(e.state.as_str()),
+            Event::Workflow(ref e) => Some(e.state.as_str()),
+        }
+    }
+
+    pub fn is_command(&self) -> bool {
+        match *self {
+            Event::Task(ref e) => e.state.is_command(),
+            Event::WorkflowInstance(ref e) => e.state.is_command(),
+            Event::Workflow(ref e) => e.state == WorkflowState::Create,
+        }
+    }
+
+    pub fn is_rejection(&self) -> bool {
+        match *self {
+            Event::Task(ref e) => e.state.is_rejection(),
+            Event::WorkflowInstance(ref e) => e.state.is_rejection(),
+            Event::Workflow(_) => false,
+        }
+    }
+
+    pub fn bpmn_process_id(&self) -> Option<&str> {
+        let bpmn_process_id = match *self {
+            Event::Task(ref e) => &e.headers.bpmn_process_id,
+            Event::WorkflowInstance(ref e) => &e.bpmn_process_id,
+            Event::Workflow(ref e) => &e.bpmn_process_id,
+        };
+        if bpmn_process_id.is_empty() {
+            None
+        } else {
+            Some(bpmn_process_id)
+        }
+    }
+
+    pub fn payload(&self) -> Option<&[u8]> {
+        match *self {
+            Event::Task(ref e) => Some(&e.payload),
+            Event::WorkflowInstance(ref e) => Some(&e.payload),
+            Event::Workflow(_) => None,
+        }
+    }
 }
 
 pub fn deserialize<'d, D: Deserialize<'d>>(data: &[u8]) -> Result<D, Error> {
@@ -251,3 +417,78 @@ pub fn deserialize<'d, D: Deserialize<'d>>(data: &[u8]) -> Result<D, Error> {
     let value = Deserialize::deserialize(&mut de)?;
     Ok(value)
 }
+
+/// Decodes a msgpack document without a schema. The data has to be exactly one value, trailing
+/// bytes are an error.
+pub fn decode_value(data: &[u8]) -> Result<::rmpv::Value, Error> {
+    let mut reader = data;
+    let value = ::rmpv::decode::read_value(&mut reader)?;
+    if !reader.is_empty() {
+        bail!("{} trailing bytes after the msgpack value", reader.len());
+    }
+    Ok(value)
+}
+
+/// Encodes a value as msgpack with named struct fields, the way the broker writes events.
+pub fn serialize<S: Serialize>(value: &S) -> Result<Vec<u8>, Error> {
+    Ok(::rmp_serde::to_vec_named(value)?)
+}
+
+/// Decodes a msgpack encoded payload document, an empty payload is returned as `null`.
+pub fn decode_payload(data: &[u8]) -> Result<Value, Error> {
+    if data.is_empty() {
+        Ok(Value::Null)
+    } else {
+        deserialize(data)
+    }
+}
+
+/// Converts a msgpack value to JSON. Binary and extension values are written as a string if they
+/// are UTF-8 and in hex otherwise, map keys which are not strings are written in msgpack notation.
+pub fn value_to_json(value: &::rmpv::Value) -> Value {
+    use rmpv::Value as V;
+
+    match *value {
+        V::Nil => Value::Null,
+        V::Boolean(b) => Value::Bool(b),
+        V::Integer(ref i) => match (i.as_u64(), i.as_i64()) {
+            (Some(u), _) => Value::Number(Number::from(u)),
+            (None, Some(i)) => Value::Number(Number::from(i)),
+            (None, None) => Value::Null,
+        },
+        V::F32(f) => Number::from_f64(f64::from(f)).map_or(Value::Null, Value::Number),
+        V::F64(f) => Number::from_f64(f).map_or(Value::Null, Value::Number),
+        V::String(ref s) => Value::String(String::from_utf8_lossy(s.as_bytes()).into_owned()),
+        V::Binary(ref data) | V::Ext(_, ref data) => match ::std::str::from_utf8(data) {
+            Ok(s) => Value::String(s.to_string()),
+            Err(_) => Value::String(hex(data)),
+        },
+        V::Array(ref values) => Value::Array(values.iter().map(value_to_json).collect()),
+        V::Map(ref entries) => Value::Object(
+            entries
+                .iter()
+                .map(|&(ref k, ref v)| {
+                    let key = match k.as_str() {
+                        Some(k) => k.to_string(),
+                        None => k.to_string(),
+                    };
+                    (key, value_to_json(v))
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn hex(data: &[u8]) -> String {
+    let hex: Vec<String> = data.iter().map(|b| format!("{:02x}", b)).collect();
+    hex.concat()
+}
+
+/// Decodes a payload for output. A payload which can't be decoded is returned as a string with the
+/// error and the payload bytes in hex, so a single odd payload doesn't stop the output.
+pub fn display_payload(data: &[u8]) -> Value {
+    match decode_payload(data) {
+        Ok(payload) => payload,
+        Err(e) => Value::String(format!("<undecodable payload: {}: {}>", e, hex(data))),
+    }
+}
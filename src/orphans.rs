@@ -0,0 +1,67 @@
+use causality::{CausalityGraph, Node};
+use data::Frame;
+use failure::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OrphanKind {
+    /// The source event position is not part of the log.
+    MissingSource,
+    /// The source event position is not before the position of the record.
+    LaterSource,
+    /// No record refers to the command as its source event.
+    UnansweredCommand,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Orphan<'g> {
+    pub kind: OrphanKind,
+    pub node: &'g Node,
+}
+
+/// Finds records with broken source event references and commands which were never processed.
+/// Source events on other partitions are not checked.
+#[derive(Debug, Default)]
+pub struct OrphanCheck {
+    graph: CausalityGraph,
+}
+
+impl OrphanCheck {
+    pub fn new() -> Self {
+        OrphanCheck::default()
+    }
+
+    pub fn add(&mut self, frame: &Frame) -> Result<(), Error> {
+        self.graph.add(frame)
+    }
+
+    pub fn orphans(&self) -> Vec<Orphan> {
+        let mut orphans = Vec::new();
+
+        for node in self.graph.nodes() {
+            if let Some(source) = node.local_source() {
+                if source >= node.position {
+                    orphans.push(Orphan {
+                        kind: OrphanKind::LaterSource,
+                        node,
+                    });
+                } else if self.graph.node(source).is_none() {
+                    orphans.push(Orphan {
+                        kind: OrphanKind::MissingSource,
+                        node,
+                    });
+                }
+            }
+
+            if node.command && self.graph.effects(node.position).is_empty() {
+                orphans.push(Orphan {
+                    kind: OrphanKind::UnansweredCommand,
+                    node,
+                });
+            }
+        }
+
+        orphans.sort_by_key(|o| o.node.position);
+        orphans
+    }
+}
@@ -1,93 +1,356 @@
 use failure::Error;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{self, BufWriter};
 use std::io::prelude::*;
+use std::str::FromStr;
 use data::Frame;
-use EventType;
 use msgpack::*;
+use record::{self, Record};
+use redact::Redaction;
+use serde_json::{self, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PayloadMode {
+    Full,
+    Truncate(usize),
+    Omit,
+}
+
+impl PayloadMode {
+    pub fn apply(&self, payload: Value) -> Option<Value> {
+        match *self {
+            PayloadMode::Full => Some(payload),
+            PayloadMode::Truncate(bytes) => Some(Value::String(truncate(payload.to_string(), bytes))),
+            PayloadMode::Omit => None,
+        }
+    }
+
+    pub fn render(&self, payload: &Value) -> Option<String> {
+        match *self {
+            PayloadMode::Full => Some(payload.to_string()),
+            PayloadMode::Truncate(bytes) => Some(truncate(payload.to_string(), bytes)),
+            PayloadMode::Omit => None,
+        }
+    }
+}
+
+impl FromStr for PayloadMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(PayloadMode::Full),
+            "omit" => Ok(PayloadMode::Omit),
+            _ => s.parse()
+                .map(PayloadMode::Truncate)
+                .map_err(|_| format_err!("Unknown payload mode '{}', expected full, omit or number of bytes", s)),
+        }
+    }
+}
+
+fn truncate(mut s: String, bytes: usize) -> String {
+    if s.len() > bytes {
+        let mut end = bytes;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+        s.push_str("...");
+    }
+    s
+}
 
 pub trait EventOutput {
     fn output(&mut self, frame: &Frame) -> Result<(), Error>;
 }
 
-pub struct StdOutput {
-    output: io::Stdout,
+/// Renders a decoded record to a destination, independent of what the destination is.
+pub trait Formatter {
+    fn format(&mut self, output: &mut Write, record: &Record) -> Result<(), Error>;
 }
 
-impl StdOutput {
-    pub fn new() -> Self {
-        StdOutput { output: io::stdout() }
+pub struct Output<W: Write> {
+    writer: W,
+    formatter: Box<Formatter>,
+    redaction: Option<Redaction>,
+}
+
+impl<W: Write> Output<W> {
+    pub fn new(writer: W, formatter: Box<Formatter>) -> Self {
+        Output {
+            writer,
+            formatter,
+            redaction: None,
+        }
+    }
+
+    pub fn set_redaction(&mut self, redaction: Option<Redaction>) {
+        self.redaction = redaction;
     }
 }
 
-impl EventOutput for StdOutput {
+impl Output<io::Stdout> {
+    pub fn stdout(formatter: Box<Formatter>) -> Self {
+        Output::new(io::stdout(), formatter)
+    }
+}
+
+impl Output<BufWriter<File>> {
+    pub fn file(filename: &str, formatter: Box<Formatter>) -> Result<Self, Error> {
+        Ok(Output::new(BufWriter::new(File::create(filename)?), formatter))
+    }
+}
+
+impl<W: Write> EventOutput for Output<W> {
     fn output(&mut self, frame: &Frame) -> Result<(), Error> {
-        let event_type: EventType = frame.entry.metadata.event_type.into();
-        write!(self.output, "{{ position: {}, key: {}, source_event_position: {}, producer: {}, type: {:?} ",
-                    frame.entry.log_entry.position,
-                    frame.entry.log_entry.key,
-                    frame.entry.log_entry.source_event_position,
-                    frame.entry.log_entry.producer,
-                    event_type)?;
-
-        match event_type {
-            EventType::Task => {
-                let event: TaskEvent = deserialize(frame.entry.event)?;
-                write!(self.output, "{:?}", event)?;
-            }
-            EventType::Workflow => {
-                let event: WorkflowEvent = deserialize(frame.entry.event)?;
-                write!(self.output, "{:?}", event)?;
-            }
-            EventType::WorkflowInstance => {
-                let event: WorkflowInstanceEvent = deserialize(frame.entry.event)?;
-                write!(self.output, "{:?}", event)?;
-            }
-            _ => {}
+        let mut record = Record::new(frame)?;
+        if let Some(ref redaction) = self.redaction {
+            record.redact(redaction)?;
         }
+        self.formatter.format(&mut self.writer, &record)
+    }
+}
+
+pub type FormatterFactory = Box<Fn() -> Box<Formatter>>;
 
-        Ok(writeln!(self.output, " }}")?)
+/// Named formatter factories, so tools can offer library user defined formats next to the
+/// builtin ones.
+#[derive(Default)]
+pub struct Formatters {
+    factories: BTreeMap<String, FormatterFactory>,
+}
+
+impl Formatters {
+    pub fn new() -> Self {
+        Formatters::default()
+    }
+
+    pub fn builtin(payload: PayloadMode, columns: Vec<String>) -> Self {
+        let mut formatters = Formatters::new();
+
+        let text_payload = payload.clone();
+        formatters.register("text", move || Box::new(TextFormatter::new(text_payload.clone())));
+        formatters.register("json", move || Box::new(JsonFormatter::new(payload.clone())));
+        let csv_columns = columns.clone();
+        formatters.register("csv", move || Box::new(CsvFormatter::new(csv_columns.clone(), ',')));
+        formatters.register("tsv", move || Box::new(CsvFormatter::new(columns.clone(), '\t')));
+
+        formatters
+    }
+
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn() -> Box<Formatter> + 'static,
+    {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.factories.keys().map(|n| n.as_str()).collect()
+    }
+
+    pub fn create(&self, name: &str) -> Result<Box<Formatter>, Error> {
+        match self.factories.get(name) {
+            Some(factory) => Ok(factory()),
+            None => Err(format_err!(
+                "Unknown output format '{}', expected one of {}",
+                name,
+                self.names().join(", ")
+            )),
+        }
     }
 }
 
-pub struct FileOutput {
-    output: BufWriter<File>,
+pub struct TextFormatter {
+    payload: PayloadMode,
 }
 
-impl FileOutput {
-    pub fn new(filename: &str) -> Result<Self, Error> {
-        Ok(FileOutput {
-            output: BufWriter::new(File::create(filename)?),
-        })
+impl TextFormatter {
+    pub fn new(payload: PayloadMode) -> Self {
+        TextFormatter { payload }
     }
 }
 
-impl EventOutput for FileOutput {
-    fn output(&mut self, frame: &Frame) -> Result<(), Error> {
-        let event_type: EventType = frame.entry.metadata.event_type.into();
-        write!(self.output, "{{ position: {}, key: {}, source_event_position: {}, producer: {}, type: {:?} ",
-               frame.entry.log_entry.position,
-               frame.entry.log_entry.key,
-               frame.entry.log_entry.source_event_position,
-               frame.entry.log_entry.producer,
-               event_type)?;
-
-        match event_type {
-            EventType::Task => {
-                let event: TaskEvent = deserialize(frame.entry.event)?;
-                write!(self.output, "{:?}", event)?;
-            }
-            EventType::Workflow => {
-                let event: WorkflowEvent = deserialize(frame.entry.event)?;
-                write!(self.output, "{:?}", event)?;
+impl Formatter for TextFormatter {
+    fn format(&mut self, output: &mut Write, record: &Record) -> Result<(), Error> {
+        let log_entry = record.log_entry;
+        write!(output, "{{ position: {}, key: {}, source_event_position: {}, producer: {}, type: {:?} ",
+               { log_entry.position },
+               { log_entry.key },
+               { log_entry.source_event_position },
+               { log_entry.producer },
+               record.event_type)?;
+
+        match record.event {
+            Some(Event::Task(ref event)) => write!(output, "{:?}", event)?,
+            Some(Event::Workflow(ref event)) => write!(output, "{:?}", event)?,
+            Some(Event::WorkflowInstance(ref event)) => write!(output, "{:?}", event)?,
+            None => {}
+        }
+
+        if let Some(payload) = record.event.as_ref().and_then(|e| e.payload()) {
+            if let Some(payload) = self.payload.render(&display_payload(payload)) {
+                write!(output, " payload: {}", payload)?;
             }
-            EventType::WorkflowInstance => {
-                let event: WorkflowInstanceEvent = deserialize(frame.entry.event)?;
-                write!(self.output, "{:?}", event)?;
+        }
+
+        Ok(writeln!(output, " }}")?)
+    }
+}
+
+pub struct JsonFormatter {
+    payload: PayloadMode,
+}
+
+impl JsonFormatter {
+    pub fn new(payload: PayloadMode) -> Self {
+        JsonFormatter { payload }
+    }
+}
+
+impl Formatter for JsonFormatter {
+    fn format(&mut self, output: &mut Write, record: &Record) -> Result<(), Error> {
+        serde_json::to_writer(&mut *output, &record.to_json(&self.payload)?)?;
+        Ok(output.write_all(b"\n")?)
+    }
+}
+
+pub fn parse_columns(columns: &str) -> Vec<String> {
+    columns
+        .split(',')
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+pub struct CsvFormatter {
+    columns: Vec<String>,
+    delimiter: char,
+    header_written: bool,
+}
+
+impl CsvFormatter {
+    pub fn new(columns: Vec<String>, delimiter: char) -> Self {
+        CsvFormatter {
+            columns,
+            delimiter,
+            header_written: false,
+        }
+    }
+
+    fn write_row(&self, output: &mut Write, fields: &[String]) -> Result<(), Error> {
+        let row: Vec<String> = fields.iter().map(|f| escape(f, self.delimiter)).collect();
+        writeln!(output, "{}", row.join(&self.delimiter.to_string()))?;
+        Ok(())
+    }
+}
+
+impl Formatter for CsvFormatter {
+    fn format(&mut self, output: &mut Write, record: &Record) -> Result<(), Error> {
+        if !self.header_written {
+            self.write_row(output, &self.columns)?;
+            self.header_written = true;
+        }
+
+        let record = record.to_json(&PayloadMode::Full)?;
+        let fields: Vec<String> = self.columns
+            .iter()
+            .map(|c| render_field(&record, c))
+            .collect();
+
+        self.write_row(output, &fields)
+    }
+}
+
+fn render_field(record: &Value, path: &str) -> String {
+    match record::lookup(record, path) {
+        None | Some(&Value::Null) => String::new(),
+        Some(&Value::String(ref s)) => s.clone(),
+        Some(v) => v.to_string(),
+    }
+}
+
+/// Quotes a CSV field if it contains the delimiter, a quote or a line break.
+pub fn escape(field: &str, delimiter: char) -> String {
+    if field.contains(delimiter) || field.contains('"') || field.contains('\n') || field.contains('\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TemplatePart {
+    Literal(String),
+    Field(String),
+}
+
+/// Formats each record as one line by replacing `{path}` placeholders with record fields, see
+/// `record::lookup` for how paths are resolved. Literal braces are written as `{{` and `}}`.
+#[derive(Debug, Clone)]
+pub struct TemplateFormatter {
+    parts: Vec<TemplatePart>,
+}
+
+impl TemplateFormatter {
+    pub fn new(template: &str) -> Result<Self, Error> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut field = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        field.push(c);
+                    }
+                    if !closed || field.trim().is_empty() {
+                        bail!("Empty or unclosed placeholder in template '{}'", template);
+                    }
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(literal.clone()));
+                        literal.clear();
+                    }
+                    parts.push(TemplatePart::Field(field.trim().to_string()));
+                }
+                '}' => bail!("Unmatched '}}' in template '{}'", template),
+                _ => literal.push(c),
             }
-            _ => {}
         }
 
-        Ok(writeln!(self.output, " }}")?)
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+
+        Ok(TemplateFormatter { parts })
+    }
+}
+
+impl Formatter for TemplateFormatter {
+    fn format(&mut self, output: &mut Write, record: &Record) -> Result<(), Error> {
+        let record = record.to_json(&PayloadMode::Full)?;
+        let mut line = String::new();
+        for part in &self.parts {
+            match *part {
+                TemplatePart::Literal(ref literal) => line.push_str(literal),
+                TemplatePart::Field(ref path) => line.push_str(&render_field(&record, path)),
+            }
+        }
+        Ok(writeln!(output, "{}", line)?)
     }
 }
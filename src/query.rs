@@ -0,0 +1,416 @@
+use data::Frame;
+use failure::Error;
+use output::PayloadMode;
+use record::{self, Record};
+use regex::Regex;
+use serde_json::{self, Value};
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// A filter expression evaluated against records, for example
+/// `type == WorkflowInstance && state == 'ACTIVITY_COMPLETED' && activityId =~ 'pay.*'`.
+///
+/// Comparisons take a field path on the left side, resolved with `record::lookup`, and a literal
+/// on the right side. Literals are quoted strings, numbers, `true`, `false`, `null` or bare words
+/// which are compared as strings. Supported operators are `==`, `!=`, `<`, `<=`, `>`, `>=`, `=~`
+/// and `!~` (regular expression match), which can be combined with `&&`, `||`, `!` and
+/// parentheses. A field path on its own matches if the field exists and is neither `null` nor
+/// `false`. Comparisons never match a field which doesn't exist, not even `!=` and `!~`, so
+/// `!field` has to be used to find records without it.
+#[derive(Debug)]
+pub struct Query {
+    expr: Expr,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Self, Error> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser { tokens, index: 0 };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            bail!("Unexpected token {:?} in query '{}'", token, query);
+        }
+        Ok(Query { expr })
+    }
+
+    pub fn matches(&self, record: &Value) -> bool {
+        self.expr.eval(record)
+    }
+
+    pub fn matches_frame(&self, frame: &Frame) -> Result<bool, Error> {
+        let record = Record::new(frame)?.to_json(&PayloadMode::Full)?;
+        Ok(self.matches(&record))
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Exists(String),
+    Compare(String, Op, Value),
+    Match(String, Regex),
+    NotMatch(String, Regex),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Expr {
+    fn eval(&self, record: &Value) -> bool {
+        match *self {
+            Expr::Or(ref a, ref b) => a.eval(record) || b.eval(record),
+            Expr::And(ref a, ref b) => a.eval(record) && b.eval(record),
+            Expr::Not(ref e) => !e.eval(record),
+            Expr::Exists(ref path) => match record::lookup(record, path) {
+                None | Some(&Value::Null) | Some(&Value::Bool(false)) => false,
+                Some(_) => true,
+            },
+            Expr::Compare(ref path, op, ref literal) => {
+                let value = match record::lookup(record, path) {
+                    Some(value) => value,
+                    None => return false,
+                };
+                let ordering = compare(value, literal);
+                match op {
+                    Op::Eq => ordering == Some(Ordering::Equal),
+                    Op::Ne => ordering != Some(Ordering::Equal),
+                    Op::Lt => ordering == Some(Ordering::Less),
+                    Op::Le => ordering == Some(Ordering::Less) || ordering == Some(Ordering::Equal),
+                    Op::Gt => ordering == Some(Ordering::Greater),
+                    Op::Ge => ordering == Some(Ordering::Greater) || ordering == Some(Ordering::Equal),
+                }
+            }
+            Expr::Match(ref path, ref regex) => is_match(record, path, regex) == Some(true),
+            Expr::NotMatch(ref path, ref regex) => is_match(record, path, regex) == Some(false),
+        }
+    }
+}
+
+/// Returns whether the field matches the regular expression, or `None` if it doesn't exist or is
+/// `null`.
+fn is_match(record: &Value, path: &str, regex: &Regex) -> Option<bool> {
+    match record::lookup(record, path) {
+        Some(&Value::String(ref s)) => Some(regex.is_match(s)),
+        None | Some(&Value::Null) => None,
+        Some(v) => Some(regex.is_match(&v.to_string())),
+    }
+}
+
+fn compare(value: &Value, literal: &Value) -> Option<Ordering> {
+    match (value, literal) {
+        (&Value::Number(ref a), &Value::Number(ref b)) => {
+            if let (Some(a), Some(b)) = (a.as_u64(), b.as_u64()) {
+                Some(a.cmp(&b))
+            } else if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+                Some(a.cmp(&b))
+            } else {
+                a.as_f64().and_then(|a| b.as_f64().and_then(|b| a.partial_cmp(&b)))
+            }
+        }
+        (&Value::String(ref a), &Value::String(ref b)) => Some(a.cmp(b)),
+        (&Value::Bool(a), &Value::Bool(b)) => Some(a.cmp(&b)),
+        (&Value::Null, &Value::Null) => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Number(String),
+    Op(&'static str),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            ' ' | '\t' | '\n' | '\r' => {
+                chars.next();
+                continue;
+            }
+            '(' => {
+                chars.next();
+                Token::Open
+            }
+            ')' => {
+                chars.next();
+                Token::Close
+            }
+            '\'' | '"' => {
+                chars.next();
+                Token::Str(quoted(&mut chars, c, query)?)
+            }
+            '&' | '|' | '=' | '!' | '<' | '>' => {
+                chars.next();
+                let next = chars.peek().cloned();
+                let (token, consumed) = match (c, next) {
+                    ('&', Some('&')) => (Token::And, true),
+                    ('|', Some('|')) => (Token::Or, true),
+                    ('=', Some('=')) => (Token::Op("=="), true),
+                    ('=', Some('~')) => (Token::Op("=~"), true),
+                    ('!', Some('=')) => (Token::Op("!="), true),
+                    ('!', Some('~')) => (Token::Op("!~"), true),
+                    ('!', _) => (Token::Not, false),
+                    ('<', Some('=')) => (Token::Op("<="), true),
+                    ('<', _) => (Token::Op("<"), false),
+                    ('>', Some('=')) => (Token::Op(">="), true),
+                    ('>', _) => (Token::Op(">"), false),
+                    _ => bail!("Unexpected character '{}' in query '{}'", c, query),
+                };
+                if consumed {
+                    chars.next();
+                }
+                token
+            }
+            c if c == '-' || c.is_digit(10) => {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == '-' || c == '.' || c == 'e' || c == 'E' || c == '+' || c.is_digit(10) {
+                        number.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                Token::Number(number)
+            }
+            c if is_word_char(c) => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if is_word_char(c) {
+                        word.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                Token::Word(word)
+            }
+            _ => bail!("Unexpected character '{}' in query '{}'", c, query),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.' || c == '-'
+}
+
+fn quoted(chars: &mut Peekable<Chars>, quote: char, query: &str) -> Result<String, Error> {
+    let mut s = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c) => s.push(c),
+                None => break,
+            },
+            c if c == quote => return Ok(s),
+            c => s.push(c),
+        }
+    }
+    bail!("Unterminated string in query '{}'", query)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    t => bail!("Expected ')' but found {:?}", t),
+                }
+            }
+            Some(Token::Word(path)) => self.comparison(path),
+            t => bail!("Expected field, '!' or '(' but found {:?}", t),
+        }
+    }
+
+    fn comparison(&mut self, path: String) -> Result<Expr, Error> {
+        let op = match self.peek() {
+            Some(&Token::Op(op)) => op,
+            _ => return Ok(Expr::Exists(path)),
+        };
+        self.next();
+
+        let literal = match self.next() {
+            Some(Token::Str(s)) => Value::String(s),
+            Some(Token::Number(n)) => serde_json::from_str(&n).map_err(|_| format_err!("Invalid number {}", n))?,
+            Some(Token::Word(w)) => match w.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => Value::Null,
+                _ => Value::String(w),
+            },
+            t => bail!("Expected value after '{}' but found {:?}", op, t),
+        };
+
+        let op = match op {
+            "=~" | "!~" => {
+                let regex = match literal {
+                    Value::String(ref s) => Regex::new(s)?,
+                    _ => bail!("Expected regular expression after '{}' but found {}", op, literal),
+                };
+                return Ok(if op == "!~" {
+                    Expr::NotMatch(path, regex)
+                } else {
+                    Expr::Match(path, regex)
+                });
+            }
+            "==" => Op::Eq,
+            "!=" => Op::Ne,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            ">" => Op::Gt,
+            _ => Op::Ge,
+        };
+
+        Ok(Expr::Compare(path, op, literal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> Value {
+        serde_json::from_str(
+            r#"{
+                "type": "Task",
+                "logEntry": {"position": 4295102400, "key": 4295102400},
+                "event": {
+                    "state": "LOCKED",
+                    "retries": -1,
+                    "lockOwner": "it's me",
+                    "headers": {"activityId": "reserve-order-items"},
+                    "payload": {"orderId": 31243, "express": false, "note": null}
+                }
+            }"#,
+        ).unwrap()
+    }
+
+    fn matches(query: &str) -> bool {
+        Query::parse(query).unwrap().matches(&record())
+    }
+
+    #[test]
+    fn and_binds_stronger_than_or() {
+        assert!(matches("type == Task || type == Workflow && state == 'CREATED'"));
+        assert!(!matches("(type == Task || type == Workflow) && state == 'CREATED'"));
+        assert!(matches("state == 'CREATED' && type == Workflow || retries < 0"));
+    }
+
+    #[test]
+    fn not() {
+        assert!(matches("!(state == 'CREATED')"));
+        assert!(!matches("!state"));
+        assert!(matches("!payload.express"));
+        assert!(matches("!payload.note"));
+        assert!(matches("!!type"));
+        assert!(matches("!missing"));
+    }
+
+    #[test]
+    fn negative_numbers() {
+        assert!(matches("retries == -1"));
+        assert!(matches("retries < 0"));
+        assert!(matches("retries > -2"));
+        assert!(matches("retries >= -1.5"));
+        assert!(!matches("retries <= -2"));
+        assert!(matches("payload.orderId > -31243"));
+    }
+
+    #[test]
+    fn quoted_strings() {
+        assert!(matches(r#"lockOwner == 'it\'s me'"#));
+        assert!(matches(r#"lockOwner == "it's me""#));
+        assert!(matches(r#"activityId == "reserve-order\-items""#));
+        assert!(matches(r#"state == 'LOCKED' && lockOwner != 'it\\s me'"#));
+        assert!(Query::parse("state == 'LOCKED").is_err());
+    }
+
+    #[test]
+    fn regular_expressions() {
+        assert!(matches("activityId =~ '^reserve-.*-items$'"));
+        assert!(!matches("activityId !~ 'order'"));
+        assert!(matches("activityId !~ '^order'"));
+        assert!(matches("payload.orderId =~ '^312'"));
+        assert!(Query::parse("activityId =~ 5").is_err());
+        assert!(Query::parse("activityId =~ '('").is_err());
+    }
+
+    #[test]
+    fn missing_fields_do_not_match() {
+        for query in &[
+            "missing == 1",
+            "missing != 1",
+            "missing < 1",
+            "missing >= 1",
+            "missing =~ '.*'",
+            "missing !~ 'x'",
+            "payload.missing != 'x'",
+            "payload.note !~ 'x'",
+        ] {
+            assert!(!matches(query), "{} matched", query);
+        }
+        assert!(matches("payload.note == null"));
+        assert!(!matches("payload.note != null"));
+        assert!(matches("payload.express != true"));
+        assert!(matches("state != 'CREATED'"));
+    }
+}
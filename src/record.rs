@@ -0,0 +1,149 @@
+use data::{Frame, LogEntry, Metadata};
+use failure::Error;
+use msgpack::*;
+use output::PayloadMode;
+use redact::Redaction;
+use serde_json::{self, Value};
+use std::borrow::Cow;
+use {EventType, Producer};
+
+pub struct Record<'f> {
+    pub log_entry: &'f LogEntry,
+    pub metadata: &'f Metadata,
+    pub event_type: EventType,
+    pub producer: Producer,
+    pub event: Option<Event>,
+    /// The encoded event body, which is converted generically for events without an `Event`.
+    pub body: Cow<'f, [u8]>,
+}
+
+impl<'f> Record<'f> {
+    pub fn new(frame: &Frame<'f>) -> Result<Self, Error> {
+        let event_type: EventType = frame.entry.metadata.event_type.into();
+        let event = Event::decode(&event_type, frame.entry.event)?;
+        Ok(Record {
+            log_entry: frame.entry.log_entry,
+            metadata: frame.entry.metadata,
+            event_type,
+            producer: frame.entry.log_entry.producer.into(),
+            event,
+            body: Cow::Borrowed(frame.entry.event),
+        })
+    }
+
+    /// Redacts the event, or the body of events without an `Event`. Bodies of unknown event types
+    /// are dropped, as they can't be redacted.
+    pub fn redact(&mut self, redaction: &Redaction) -> Result<(), Error> {
+        match self.event {
+            Some(ref mut event) => redaction.event(event),
+            None => {
+                self.body = match redaction.encoded_event(&self.event_type, &self.body)? {
+                    Some(body) => Cow::Owned(body),
+                    None => Cow::Borrowed(&[]),
+                };
+                Ok(())
+            }
+        }
+    }
+
+    pub fn to_json(&self, payload_mode: &PayloadMode) -> Result<Value, Error> {
+        let mut event = match self.event {
+            Some(ref event) => serde_json::to_value(event)?,
+            None => self.body_json(payload_mode),
+        };
+
+        if let Some(ref e) = self.event {
+            if let Some(event) = event.as_object_mut() {
+                if let Event::Workflow(ref workflow) = *e {
+                    let bpmn_xml = String::from_utf8_lossy(&workflow.bpmn_xml).into_owned();
+                    event.insert("bpmnXml".to_string(), Value::String(bpmn_xml));
+                }
+
+                match e.payload() {
+                    Some(payload) => match payload_mode.apply(display_payload(payload)) {
+                        Some(payload) => event.insert("payload".to_string(), payload),
+                        None => event.remove("payload"),
+                    },
+                    None => None,
+                };
+            }
+        }
+
+        let mut record = serde_json::Map::new();
+        record.insert("type".to_string(), serde_json::to_value(&self.event_type)?);
+        record.insert("producer".to_string(), serde_json::to_value(&self.producer)?);
+        record.insert("logEntry".to_string(), serde_json::to_value(self.log_entry)?);
+        record.insert("metadata".to_string(), serde_json::to_value(self.metadata)?);
+        record.insert("event".to_string(), event);
+        Ok(Value::Object(record))
+    }
+
+    /// Converts the body of an event without an `Event`, an empty body is `null`.
+    fn body_json(&self, payload_mode: &PayloadMode) -> Value {
+        if self.body.is_empty() {
+            return Value::Null;
+        }
+        let body = match decode_value(&self.body) {
+            Ok(body) => body,
+            Err(e) => return Value::String(format!("<undecodable body: {}>", e)),
+        };
+
+        let mut json = value_to_json(&body);
+        if let (Some(entries), Some(object)) = (body.as_map(), json.as_object_mut()) {
+            for &(ref key, ref value) in entries {
+                if let (Some("payload"), &::rmpv::Value::Binary(ref payload)) = (key.as_str(), value) {
+                    match payload_mode.apply(display_payload(payload)) {
+                        Some(payload) => object.insert("payload".to_string(), payload),
+                        None => object.remove("payload"),
+                    };
+                }
+            }
+        }
+        json
+    }
+}
+
+const EVENT_SCOPES: &[&str] = &["", "/event", "/event/headers"];
+const HEADER_SCOPES: &[&str] = &["/logEntry", "/metadata"];
+
+/// Log entry and metadata fields which can be used without qualification. `version` is left out, as
+/// it is also the name of an event field.
+const HEADER_FIELDS: &[&str] = &[
+    "position",
+    "raftTerm",
+    "producer",
+    "sourceEventStreamPartition",
+    "sourceEventPosition",
+    "key",
+    "metadataLength",
+    "requestStreamId",
+    "requestId",
+    "subscriptionId",
+    "protocolVersion",
+    "eventType",
+    "incidentKey",
+];
+
+/// Resolves a dotted field path like `state` or `payload.orderId` against a record created by
+/// `Record::to_json`. Paths are looked up at the top level of the record first, then in the event
+/// and the task headers. Log entry and metadata fields are only looked up if they can't be confused
+/// with an event field, other ones have to be qualified like `logEntry.version`. `version` of a
+/// task refers to the workflow definition version in its headers.
+pub fn lookup<'v>(record: &'v Value, path: &str) -> Option<&'v Value> {
+    let is_task = record.get("type").and_then(|t| t.as_str()) == Some("Task");
+    let path = match path {
+        "taskType" => "event.type",
+        "version" if is_task => "event.headers.workflowDefinitionVersion",
+        _ => path,
+    };
+    let pointer = format!("/{}", path.replace('.', "/"));
+
+    let name = path.split('.').next().unwrap_or(path);
+    let header_scopes = if HEADER_FIELDS.contains(&name) { HEADER_SCOPES } else { &[] };
+
+    EVENT_SCOPES
+        .iter()
+        .chain(header_scopes)
+        .filter_map(|scope| record.pointer(&format!("{}{}", scope, pointer)))
+        .next()
+}
@@ -0,0 +1,334 @@
+use failure::Error;
+use msgpack::{self, Event};
+use rmpv::{self, Value};
+use serde_bytes::ByteBuf;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+#[allow(deprecated)]
+use std::hash::{BuildHasher, Hasher, SipHasher};
+use EventType;
+
+/// Fields of event bodies which can contain personal data. Payload documents and custom header
+/// maps keep their structure, only the values inside them are replaced.
+const PERSONAL_FIELDS: &[&str] = &["payload", "customHeaders", "errorMessage"];
+
+/// Replaces personal data in payloads, custom task headers and incident error messages. Documents
+/// keep their structure and value types, only strings, numbers and binary values are replaced:
+/// either by a hash of the value, so equal values stay equal across events, or by a placeholder of
+/// the same type. Hashed booleans are a bit of the hash of the value, placeholders are `false`.
+///
+/// Hashes are keyed with a salt, so that values can't be recovered by hashing guesses without
+/// knowing it. Without a given salt a random one is used, then hashes differ between runs.
+///
+/// Replaced values usually have a different encoded length than the original ones. A redacted
+/// segment keeps the positions of its log entries, but the frames move, so positions no longer
+/// follow the frame offsets and `anomalies` reports position gaps for them.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Redaction {
+    /// SipHash-2-4 keys derived from the salt.
+    Hash(u64, u64),
+    Placeholder,
+}
+
+impl fmt::Debug for Redaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Redaction::Hash(..) => f.write_str("Hash"),
+            Redaction::Placeholder => f.write_str("Placeholder"),
+        }
+    }
+}
+
+impl Redaction {
+    /// Creates a redaction for the mode `hash` or `placeholder`.
+    pub fn new(mode: &str, salt: Option<&str>) -> Result<Self, Error> {
+        match (mode, salt) {
+            ("hash", Some(salt)) => Ok(Redaction::Hash(
+                sip_hash(0, 0, &[salt.as_bytes(), &[0][..]]),
+                sip_hash(0, 0, &[salt.as_bytes(), &[1][..]]),
+            )),
+            ("hash", None) => Ok(Redaction::Hash(random_key(), random_key())),
+            ("placeholder", None) => Ok(Redaction::Placeholder),
+            ("placeholder", Some(_)) => bail!("A salt can only be used with the hash mode"),
+            _ => bail!("Unknown redaction mode '{}', expected hash or placeholder", mode),
+        }
+    }
+
+    fn hash(&self, data: &[u8]) -> Option<u64> {
+        match *self {
+            Redaction::Hash(k0, k1) => Some(sip_hash(k0, k1, &[data])),
+            Redaction::Placeholder => None,
+        }
+    }
+
+    pub fn string(&self, s: &str) -> String {
+        match self.hash(s.as_bytes()) {
+            Some(hash) => format!("{:016x}", hash),
+            None => "<redacted>".to_string(),
+        }
+    }
+
+    pub fn value(&self, value: Value) -> Value {
+        let hash = match value {
+            Value::Boolean(_) | Value::Integer(_) | Value::F32(_) | Value::F64(_) => {
+                self.hash(value.to_string().as_bytes())
+            }
+            Value::Binary(ref data) | Value::Ext(_, ref data) => self.hash(data),
+            _ => None,
+        };
+        match value {
+            Value::Nil => Value::Nil,
+            Value::Boolean(_) => Value::Boolean(hash.map_or(0, |h| h & 1) == 1),
+            // hashed integers stay in the range of a Java long
+            Value::Integer(_) => Value::from(hash.map_or(0, |h| h >> 1)),
+            Value::F32(_) => Value::F32(hash.map_or(0.0, |h| (h >> 40) as f32 / (1u64 << 24) as f32)),
+            Value::F64(_) => Value::F64(hash.map_or(0.0, |h| (h >> 11) as f64 / (1u64 << 53) as f64)),
+            Value::String(s) => match s.as_str() {
+                Some(s) => Value::from(self.string(s)),
+                None => Value::from(self.string(&String::from_utf8_lossy(s.as_bytes()))),
+            },
+            Value::Binary(_) => Value::Binary(hash.map_or_else(Vec::new, bytes)),
+            Value::Array(values) => Value::Array(values.into_iter().map(|v| self.value(v)).collect()),
+            Value::Map(entries) => Value::Map(entries.into_iter().map(|(k, v)| (k, self.value(v))).collect()),
+            Value::Ext(ty, _) => Value::Ext(ty, hash.map_or_else(Vec::new, bytes)),
+        }
+    }
+
+    /// Redacts a msgpack encoded payload, an empty payload stays empty.
+    pub fn payload(&self, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        if payload.is_empty() {
+            return Ok(Vec::new());
+        }
+        encode(&self.value(msgpack::decode_value(payload)?))
+    }
+
+    pub fn event(&self, event: &mut Event) -> Result<(), Error> {
+        match *event {
+            Event::Task(ref mut e) => {
+                e.payload = ByteBuf::from(self.payload(&e.payload)?);
+                for value in e.custom_headers.values_mut() {
+                    *value = self.string(value);
+                }
+            }
+            Event::WorkflowInstance(ref mut e) => {
+                e.payload = ByteBuf::from(self.payload(&e.payload)?);
+            }
+            Event::Workflow(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Redacts an encoded event body without decoding it into an event, so that fields which are
+    /// not part of `Event` are kept. Only the values of the personal data fields in the top level
+    /// map of the body are replaced, all other keys and values are copied byte for byte.
+    ///
+    /// Returns `None` for unknown event types, which can contain personal data in other fields.
+    pub fn encoded_event(&self, event_type: &EventType, data: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        if let EventType::Unknown(_) = *event_type {
+            return Ok(None);
+        }
+        // noop events have an empty body
+        if data.is_empty() {
+            return Ok(Some(Vec::new()));
+        }
+
+        let (length, header) = map_header(data)?;
+        let mut redacted = data[..header].to_vec();
+        let mut rest = &data[header..];
+        for _ in 0..length {
+            let key_start = data.len() - rest.len();
+            let key = rmpv::decode::read_value(&mut rest)?;
+            let value_start = data.len() - rest.len();
+            let value = rmpv::decode::read_value(&mut rest)?;
+            let value_end = data.len() - rest.len();
+
+            redacted.extend_from_slice(&data[key_start..value_start]);
+            match key.as_str() {
+                Some(key) if PERSONAL_FIELDS.contains(&key) => {
+                    let value = match value {
+                        Value::Binary(ref payload) if key == "payload" => Value::Binary(self.payload(payload)?),
+                        value => self.value(value),
+                    };
+                    redacted.extend_from_slice(&encode(&value)?);
+                }
+                _ => redacted.extend_from_slice(&data[value_start..value_end]),
+            }
+        }
+        if !rest.is_empty() {
+            bail!("{} trailing bytes after the msgpack value", rest.len());
+        }
+
+        Ok(Some(redacted))
+    }
+}
+
+/// Returns the number of entries and the header length of a msgpack map.
+fn map_header(data: &[u8]) -> Result<(usize, usize), Error> {
+    let be = |bytes: &[u8]| bytes.iter().fold(0, |n, &b| (n << 8) | b as usize);
+    match data.first() {
+        Some(&marker) if marker & 0xf0 == 0x80 => Ok(((marker & 0x0f) as usize, 1)),
+        Some(&0xde) if data.len() >= 3 => Ok((be(&data[1..3]), 3)),
+        Some(&0xdf) if data.len() >= 5 => Ok((be(&data[1..5]), 5)),
+        _ => bail!("Event body is not a msgpack map"),
+    }
+}
+
+fn encode(value: &Value) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    rmpv::encode::write_value(&mut data, value)?;
+    Ok(data)
+}
+
+#[allow(deprecated)]
+fn sip_hash(k0: u64, k1: u64, data: &[&[u8]]) -> u64 {
+    let mut hasher = SipHasher::new_with_keys(k0, k1);
+    for data in data {
+        hasher.write(data);
+    }
+    hasher.finish()
+}
+
+/// Returns a random key, the hashers of `RandomState` are seeded by the operating system.
+fn random_key() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+fn bytes(value: u64) -> Vec<u8> {
+    (0..8).map(|i| (value >> (i * 8)) as u8).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem;
+
+    fn map(entries: Vec<(&str, Value)>) -> Value {
+        Value::Map(entries.into_iter().map(|(k, v)| (Value::from(k), v)).collect())
+    }
+
+    fn payload() -> Value {
+        map(vec![
+            ("name", Value::from("Alice")),
+            ("age", Value::from(42)),
+            ("score", Value::F64(1.5)),
+            ("vip", Value::Boolean(true)),
+            ("tags", Value::Array(vec![Value::from("x")])),
+            ("none", Value::Nil),
+        ])
+    }
+
+    fn task_body() -> Vec<u8> {
+        encode(&map(vec![
+            ("state", Value::from("CREATED")),
+            ("lockTime", Value::from(1234)),
+            ("type", Value::from("payment")),
+            ("customHeaders", map(vec![("account", Value::from("DE123"))])),
+            ("retries", Value::from(3)),
+            ("payload", Value::Binary(encode(&payload()).unwrap())),
+        ])).unwrap()
+    }
+
+    /// Returns the keys and the encoded values of a msgpack map.
+    fn entries(data: &[u8]) -> Vec<(Value, Vec<u8>)> {
+        let (length, header) = map_header(data).unwrap();
+        let mut rest = &data[header..];
+        (0..length)
+            .map(|_| {
+                let key = rmpv::decode::read_value(&mut rest).unwrap();
+                let start = data.len() - rest.len();
+                rmpv::decode::read_value(&mut rest).unwrap();
+                (key, data[start..data.len() - rest.len()].to_vec())
+            })
+            .collect()
+    }
+
+    fn field<'v>(value: &'v Value, key: &str) -> &'v Value {
+        match *value {
+            Value::Map(ref entries) => &entries.iter().find(|e| e.0.as_str() == Some(key)).unwrap().1,
+            _ => panic!("{:?} is not a map", value),
+        }
+    }
+
+    fn decode(data: &[u8]) -> Value {
+        msgpack::decode_value(data).unwrap()
+    }
+
+    fn same_type(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (&Value::Array(ref a), &Value::Array(ref b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_type(a, b))
+            }
+            (&Value::Map(ref a), &Value::Map(ref b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.0 == b.0 && same_type(&a.1, &b.1))
+            }
+            (&Value::Integer(_), &Value::Integer(_)) => true,
+            _ => mem::discriminant(a) == mem::discriminant(b),
+        }
+    }
+
+    #[test]
+    fn only_personal_values_are_replaced() {
+        let body = task_body();
+        for redaction in &[Redaction::new("hash", Some("salt")).unwrap(), Redaction::Placeholder] {
+            let redacted = redaction.encoded_event(&EventType::Task, &body).unwrap().unwrap();
+
+            let original = entries(&body);
+            let redacted = entries(&redacted);
+            assert_eq!(redacted.len(), original.len());
+            for (&(ref key, ref value), &(ref redacted_key, ref redacted_value)) in original.iter().zip(&redacted) {
+                assert_eq!(key, redacted_key);
+                if PERSONAL_FIELDS.contains(&key.as_str().unwrap()) {
+                    assert_ne!(value, redacted_value, "{} of {:?}", key, redaction);
+                } else {
+                    assert_eq!(value, redacted_value, "{} of {:?}", key, redaction);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn payload_and_custom_headers_keep_keys_and_types() {
+        let redaction = Redaction::new("hash", Some("salt")).unwrap();
+        let redacted = redaction.encoded_event(&EventType::Task, &task_body()).unwrap().unwrap();
+        let redacted = decode(&redacted);
+
+        let payload_data = match *field(&redacted, "payload") {
+            Value::Binary(ref data) => data.clone(),
+            ref other => panic!("Unexpected payload {:?}", other),
+        };
+        let redacted_payload = decode(&payload_data);
+        assert!(same_type(&redacted_payload, &payload()), "{:?}", redacted_payload);
+        assert_ne!(field(&redacted_payload, "name"), field(&payload(), "name"));
+        assert_ne!(field(&redacted_payload, "age"), field(&payload(), "age"));
+        assert_ne!(field(&redacted_payload, "score"), field(&payload(), "score"));
+        assert_ne!(field(&redacted_payload, "tags"), field(&payload(), "tags"));
+        assert_eq!(field(&redacted_payload, "none"), &Value::Nil);
+
+        let headers = field(&redacted, "customHeaders");
+        assert!(same_type(headers, &map(vec![("account", Value::from(""))])), "{:?}", headers);
+        assert_eq!(field(headers, "account").as_str(), Some(redaction.string("DE123").as_str()));
+    }
+
+    #[test]
+    fn equal_values_are_equally_hashed() {
+        let redaction = Redaction::new("hash", Some("salt")).unwrap();
+        assert_eq!(redaction.value(payload()), redaction.value(payload()));
+        assert_ne!(
+            redaction.value(payload()),
+            Redaction::new("hash", Some("other")).unwrap().value(payload())
+        );
+    }
+
+    #[test]
+    fn booleans_are_replaced() {
+        assert_eq!(Redaction::Placeholder.value(Value::Boolean(true)), Value::Boolean(false));
+
+        // the hashed value of a boolean depends on the salt instead of the value
+        let hashed: Vec<Value> = (0..16)
+            .map(|salt| Redaction::new("hash", Some(&salt.to_string())).unwrap())
+            .map(|redaction| redaction.value(Value::Boolean(true)))
+            .collect();
+        assert!(hashed.contains(&Value::Boolean(false)));
+        assert!(hashed.contains(&Value::Boolean(true)));
+    }
+}
@@ -0,0 +1,145 @@
+use data::Frame;
+use failure::Error;
+use msgpack::Event;
+use std::collections::{BTreeMap, HashMap};
+use EventType;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandInfo {
+    pub position: u64,
+    pub key: u64,
+    #[serde(rename = "type")]
+    pub event_type: EventType,
+    pub state: Option<&'static str>,
+    pub bpmn_process_id: Option<String>,
+    pub request_stream_id: i32,
+    pub request_id: u64,
+}
+
+impl CommandInfo {
+    fn new(frame: &Frame, event_type: EventType, event: &Event) -> Self {
+        CommandInfo {
+            position: frame.entry.log_entry.position,
+            key: frame.entry.log_entry.key,
+            event_type,
+            state: event.state(),
+            bpmn_process_id: event.bpmn_process_id().map(|b| b.to_string()),
+            request_stream_id: frame.entry.metadata.request_stream_id,
+            request_id: frame.entry.metadata.request_id,
+        }
+    }
+
+    /// Returns the client request as request stream id and request id, if the record was written
+    /// for a client request.
+    pub fn request(&self) -> Option<(i32, u64)> {
+        if self.request_stream_id >= 0 && self.request_id < ::std::u64::MAX {
+            Some((self.request_stream_id, self.request_id))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rejection {
+    pub rejection: CommandInfo,
+    /// The rejected command, `None` if it is not part of the analysed log.
+    pub command: Option<CommandInfo>,
+}
+
+impl Rejection {
+    pub fn bpmn_process_id(&self) -> Option<&str> {
+        self.rejection
+            .bpmn_process_id
+            .as_ref()
+            .or_else(|| self.command.as_ref().and_then(|c| c.bpmn_process_id.as_ref()))
+            .map(|b| b.as_str())
+    }
+
+    pub fn request(&self) -> Option<(i32, u64)> {
+        self.command
+            .as_ref()
+            .and_then(|c| c.request())
+            .or_else(|| self.rejection.request())
+    }
+}
+
+/// Pairs every rejection with the command that caused it, using the source event position of the
+/// rejection.
+#[derive(Debug, Default)]
+pub struct RejectionAnalysis {
+    commands: HashMap<u64, CommandInfo>,
+    rejections: Vec<(CommandInfo, Option<u64>)>,
+}
+
+impl RejectionAnalysis {
+    pub fn new() -> Self {
+        RejectionAnalysis::default()
+    }
+
+    pub fn add(&mut self, frame: &Frame) -> Result<(), Error> {
+        let event_type: EventType = frame.entry.metadata.event_type.into();
+        if let Some(event) = Event::decode(&event_type, frame.entry.event)? {
+            let info = CommandInfo::new(frame, event_type, &event);
+            if event.is_command() {
+                self.commands.insert(info.position, info);
+            } else if event.is_rejection() {
+                self.rejections.push((info, frame.entry.log_entry.source_event_position()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns all rejections ordered by position.
+    pub fn rejections(&self) -> Vec<Rejection> {
+        let mut rejections: Vec<Rejection> = self.rejections
+            .iter()
+            .map(|&(ref rejection, source)| Rejection {
+                rejection: rejection.clone(),
+                command: source.and_then(|s| self.commands.get(&s)).cloned(),
+            })
+            .collect();
+        rejections.sort_by_key(|r| r.rejection.position);
+        rejections
+    }
+
+    pub fn by_type(&self) -> BTreeMap<String, usize> {
+        count(self.rejections(), |r| r.rejection.state.unwrap_or("").to_string())
+    }
+
+    pub fn by_workflow(&self) -> BTreeMap<String, usize> {
+        count(self.rejections(), |r| r.bpmn_process_id().unwrap_or("").to_string())
+    }
+
+    /// Groups the rejections by the client request of the rejected command.
+    pub fn by_request(&self) -> BTreeMap<(i32, u64), Vec<Rejection>> {
+        let mut requests = BTreeMap::new();
+        for rejection in self.rejections() {
+            if let Some(request) = rejection.request() {
+                requests.entry(request).or_insert_with(Vec::new).push(rejection);
+            }
+        }
+        requests
+    }
+
+    /// Counts the rejections per client request stream, which identifies a client connection.
+    pub fn by_request_stream(&self) -> BTreeMap<i32, usize> {
+        let mut streams = BTreeMap::new();
+        for rejection in self.rejections() {
+            if let Some((request_stream_id, _)) = rejection.request() {
+                *streams.entry(request_stream_id).or_insert(0) += 1;
+            }
+        }
+        streams
+    }
+}
+
+fn count<F: Fn(&Rejection) -> String>(rejections: Vec<Rejection>, key: F) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for rejection in &rejections {
+        *counts.entry(key(rejection)).or_insert(0) += 1;
+    }
+    counts
+}
@@ -0,0 +1,142 @@
+use check::{self, Violation};
+use data::*;
+use decode::Decoder;
+use failure::Error;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairReport {
+    pub original_size: usize,
+    pub repaired_size: usize,
+    pub cut_bytes: usize,
+    pub frames_kept: usize,
+    /// Number of readable frames which were cut off, a trailing partial frame is not counted.
+    pub frames_cut: usize,
+    pub last_valid_position: Option<u64>,
+    /// The violations in the segment header and the part which was cut off, and the content
+    /// violations of kept frames, which are not repaired.
+    pub violations: Vec<Violation>,
+}
+
+impl RepairReport {
+    pub fn is_repaired(&self) -> bool {
+        self.cut_bytes > 0
+    }
+}
+
+/// Creates a copy of the segment which ends after the last structurally valid frame. The size in
+/// the segment header is updated and the cut off bytes are zeroed, the file length stays the same.
+/// Frames with an unexpected position or an undecodable event are kept, as the frames after them
+/// can still be read.
+pub fn repair_segment(data: &[u8]) -> Result<(Vec<u8>, RepairReport), Error> {
+    let check = check::check_segment(data);
+    if check.valid_end < BLOCK_SIZE {
+        bail!(
+            "Unable to repair segment with an invalid header: {}",
+            check.violations
+                .first()
+                .map(|v| v.message.as_str())
+                .unwrap_or("unknown error")
+        );
+    }
+
+    let original_size = {
+        let mut decoder = Decoder::new(data);
+        let segment: &FsLogSegment = decoder.read_type()?;
+        segment.size as usize
+    };
+
+    let mut repaired = data.to_vec();
+    let end = original_size.min(repaired.len());
+    let repaired_size = check.valid_end;
+    for byte in &mut repaired[repaired_size..end] {
+        *byte = 0;
+    }
+    set_fs_log_segment_size(&mut repaired, repaired_size as u32)?;
+
+    let violations = check.violations
+        .into_iter()
+        .filter(|v| v.offset >= repaired_size || v.offset == 0 || !v.structural)
+        .collect();
+
+    let report = RepairReport {
+        original_size,
+        repaired_size,
+        cut_bytes: original_size.saturating_sub(repaired_size),
+        frames_kept: check.valid_frames,
+        frames_cut: check.frames - check.valid_frames,
+        last_valid_position: check.valid_position,
+        violations,
+    };
+
+    Ok((repaired, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encode::SegmentWriter;
+    use std::mem;
+
+    const FIRST_POSITION: u64 = 1 << 32;
+    /// Length of a noop frame including its padding.
+    const NOOP_FRAME: usize = 96;
+
+    fn noop_segment(frames: u64) -> Vec<u8> {
+        let mut writer = SegmentWriter::new(0, 0, 1 << 16);
+        for i in 0..frames {
+            writer.append(&LogEntry::new(FIRST_POSITION + i, 0), &Metadata::new(8), &[]).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn size(data: &[u8]) -> usize {
+        fs_log_segment(data).unwrap().size as usize
+    }
+
+    #[test]
+    fn appended_garbage_is_cut_off() {
+        let mut data = noop_segment(3);
+        let valid_size = data.len();
+        data.extend_from_slice(&[0xab; 64]);
+        let original_size = data.len();
+        set_fs_log_segment_size(&mut data, original_size as u32).unwrap();
+
+        let (repaired, report) = repair_segment(&data).unwrap();
+        assert_eq!(report.original_size, original_size);
+        assert_eq!(report.repaired_size, valid_size);
+        assert_eq!(report.cut_bytes, 64);
+        assert_eq!(report.frames_kept, 3);
+        assert_eq!(report.frames_cut, 0);
+        assert_eq!(report.last_valid_position, Some(FIRST_POSITION + 2));
+        assert_eq!(report.violations.len(), 1);
+        assert_eq!(report.violations[0].offset, valid_size);
+
+        assert_eq!(repaired.len(), data.len());
+        assert_eq!(size(&repaired), valid_size);
+        let header = mem::size_of::<FsLogSegment>();
+        assert_eq!(&repaired[header..valid_size], &data[header..valid_size]);
+        assert!(repaired[valid_size..].iter().all(|&b| b == 0));
+        assert!(check::check_segment(&repaired).is_valid());
+    }
+
+    #[test]
+    fn frames_after_a_broken_frame_are_cut_off() {
+        let mut data = noop_segment(3);
+        let broken = BLOCK_SIZE + NOOP_FRAME;
+        let sbe_header = broken + mem::size_of::<DataFrame>() + mem::size_of::<LogEntry>();
+        data[sbe_header + 2] ^= 0xff;
+
+        let (repaired, report) = repair_segment(&data).unwrap();
+        assert_eq!(report.repaired_size, broken);
+        assert_eq!(report.cut_bytes, 2 * NOOP_FRAME);
+        assert_eq!(report.frames_kept, 1);
+        assert_eq!(report.frames_cut, 2);
+        assert_eq!(report.last_valid_position, Some(FIRST_POSITION));
+
+        assert_eq!(size(&repaired), broken);
+        assert_eq!(&repaired[BLOCK_SIZE..broken], &data[BLOCK_SIZE..broken]);
+        assert!(repaired[broken..].iter().all(|&b| b == 0));
+        assert!(check::check_segment(&repaired).is_valid());
+    }
+}
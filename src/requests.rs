@@ -0,0 +1,154 @@
+use causality::{CausalityGraph, Node};
+use data::Frame;
+use failure::Error;
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+
+/// The records of one client request. The request metadata is only written to the records the
+/// broker received or answered, the response and follow-ups are found through the source event
+/// positions of the records.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientRequest<'g> {
+    pub request_stream_id: i32,
+    pub request_id: u64,
+    /// The records carrying the request metadata, ordered by position.
+    pub records: Vec<&'g Node>,
+    /// The first record caused by the first record of the request, usually the resulting event
+    /// or rejection of the command.
+    pub response: Option<&'g Node>,
+    pub rejected: bool,
+    /// All other records caused directly or indirectly by the request, ordered by position.
+    pub follow_ups: Vec<&'g Node>,
+}
+
+impl<'g> ClientRequest<'g> {
+    pub fn command(&self) -> Option<&'g Node> {
+        self.records.iter().find(|n| n.command).or_else(|| self.records.first()).cloned()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestStreamStatistics {
+    pub frames: usize,
+    pub bytes: usize,
+    pub requests: usize,
+    pub commands: usize,
+    pub rejections: usize,
+    pub subscriptions: BTreeSet<u64>,
+    pub first_position: Option<u64>,
+    pub last_position: Option<u64>,
+}
+
+/// Groups records by the client request they belong to, using `request_stream_id` and
+/// `request_id` of their metadata, and collects statistics per request stream, which is one
+/// client connection.
+#[derive(Debug, Default)]
+pub struct RequestAnalysis {
+    graph: CausalityGraph,
+    requests: BTreeMap<(i32, u64), Vec<u64>>,
+    streams: BTreeMap<i32, RequestStreamStatistics>,
+}
+
+impl RequestAnalysis {
+    pub fn new() -> Self {
+        RequestAnalysis::default()
+    }
+
+    pub fn add(&mut self, frame: &Frame) -> Result<(), Error> {
+        self.graph.add(frame)?;
+
+        let metadata = frame.entry.metadata;
+        let request_stream_id = metadata.request_stream_id;
+        let request_id = metadata.request_id;
+        let subscription_id = metadata.subscription_id;
+        let position = frame.entry.log_entry.position;
+
+        if request_stream_id < 0 {
+            return Ok(());
+        }
+
+        let node = self.graph.node(position);
+        let stream = self.streams.entry(request_stream_id).or_insert_with(Default::default);
+        stream.frames += 1;
+        stream.bytes += frame.data_frame.length as usize;
+        if node.map(|n| n.command).unwrap_or(false) {
+            stream.commands += 1;
+        }
+        if node.map(|n| n.rejection).unwrap_or(false) {
+            stream.rejections += 1;
+        }
+        if subscription_id < ::std::u64::MAX {
+            stream.subscriptions.insert(subscription_id);
+        }
+        stream.first_position = stream.first_position.or(Some(position));
+        stream.last_position = Some(position);
+
+        if request_id < ::std::u64::MAX {
+            let records = self.requests.entry((request_stream_id, request_id)).or_insert_with(Vec::new);
+            if records.is_empty() {
+                stream.requests += 1;
+            }
+            records.push(position);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the client requests ordered by request stream and request id.
+    pub fn requests(&self) -> Vec<ClientRequest> {
+        self.requests
+            .iter()
+            .map(|(&(request_stream_id, request_id), positions)| {
+                let mut records: Vec<&Node> = positions.iter().filter_map(|p| self.graph.node(*p)).collect();
+                records.sort_by_key(|n| n.position);
+
+                let response = records
+                    .first()
+                    .and_then(|first| {
+                        self.graph
+                            .effects(first.position)
+                            .into_iter()
+                            .find(|e| !positions.contains(&e.position))
+                    });
+
+                let mut excluded: HashSet<u64> = positions.iter().cloned().collect();
+                excluded.extend(response.map(|r| r.position));
+                let mut follow_ups = self.follow_ups(&records, &excluded);
+                follow_ups.sort_by_key(|n| n.position);
+
+                ClientRequest {
+                    request_stream_id,
+                    request_id,
+                    rejected: records.iter().chain(response.iter()).any(|n| n.rejection),
+                    records,
+                    response,
+                    follow_ups,
+                }
+            })
+            .collect()
+    }
+
+    pub fn request_streams(&self) -> &BTreeMap<i32, RequestStreamStatistics> {
+        &self.streams
+    }
+
+    fn follow_ups(&self, records: &[&Node], excluded: &HashSet<u64>) -> Vec<&Node> {
+        let mut visited: HashSet<u64> = records.iter().map(|n| n.position).collect();
+        let mut queue: VecDeque<u64> = visited.iter().cloned().collect();
+        let mut follow_ups = Vec::new();
+
+        while let Some(position) = queue.pop_front() {
+            for effect in self.graph.effects(position) {
+                if visited.insert(effect.position) {
+                    queue.push_back(effect.position);
+                    if !excluded.contains(&effect.position) {
+                        follow_ups.push(effect);
+                    }
+                }
+            }
+        }
+
+        follow_ups
+    }
+}
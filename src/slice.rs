@@ -0,0 +1,93 @@
+use data::Frame;
+use failure::Error;
+use msgpack::Event;
+use std::collections::HashSet;
+
+#[derive(Debug)]
+enum Selection {
+    Range { from: Option<u64>, to: Option<u64> },
+    Instance(i64),
+}
+
+/// Selects the frames which are copied into a slice of a log, either all frames in a position
+/// range or the frames of one workflow instance.
+///
+/// For an instance the workflow it was created from is selected as well, so that the slice can be
+/// read on its own. The workflow keys are only known after `prepare` has been called for every
+/// frame of the log, before the frames are checked with `matches`.
+#[derive(Debug)]
+pub struct SliceFilter {
+    selection: Selection,
+    workflow_keys: HashSet<i64>,
+}
+
+impl SliceFilter {
+    /// Selects the frames with a position between `from` and `to`, both inclusive.
+    pub fn range(from: Option<u64>, to: Option<u64>) -> Self {
+        SliceFilter {
+            selection: Selection::Range { from, to },
+            workflow_keys: HashSet::new(),
+        }
+    }
+
+    pub fn instance(workflow_instance_key: i64) -> Self {
+        SliceFilter {
+            selection: Selection::Instance(workflow_instance_key),
+            workflow_keys: HashSet::new(),
+        }
+    }
+
+    pub fn needs_prepare(&self) -> bool {
+        match self.selection {
+            Selection::Instance(_) => true,
+            Selection::Range { .. } => false,
+        }
+    }
+
+    pub fn prepare(&mut self, frame: &Frame) -> Result<(), Error> {
+        let workflow_instance_key = match self.selection {
+            Selection::Instance(key) => key,
+            Selection::Range { .. } => return Ok(()),
+        };
+
+        if let Some(event) = decode(frame)? {
+            if is_related(frame, &event, workflow_instance_key) {
+                match event {
+                    Event::WorkflowInstance(ref event) => self.workflow_keys.insert(event.workflow_key),
+                    Event::Task(ref event) => self.workflow_keys.insert(event.headers.workflow_key),
+                    Event::Workflow(_) => false,
+                };
+            }
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, frame: &Frame) -> Result<bool, Error> {
+        match self.selection {
+            Selection::Range { from, to } => {
+                let position = frame.entry.log_entry.position;
+                Ok(from.map(|from| position >= from).unwrap_or(true) && to.map(|to| position <= to).unwrap_or(true))
+            }
+            Selection::Instance(workflow_instance_key) => Ok(match decode(frame)? {
+                Some(Event::Workflow(_)) => self.workflow_keys.contains(&(frame.entry.log_entry.key as i64)),
+                Some(ref event) => is_related(frame, event, workflow_instance_key),
+                None => false,
+            }),
+        }
+    }
+}
+
+fn decode(frame: &Frame) -> Result<Option<Event>, Error> {
+    Event::decode(&frame.entry.metadata.event_type.into(), frame.entry.event)
+}
+
+fn is_related(frame: &Frame, event: &Event, workflow_instance_key: i64) -> bool {
+    match *event {
+        Event::WorkflowInstance(ref event) => {
+            event.workflow_instance_key == workflow_instance_key ||
+                frame.entry.log_entry.key as i64 == workflow_instance_key
+        }
+        Event::Task(ref event) => event.headers.workflow_instance_key == workflow_instance_key,
+        Event::Workflow(_) => false,
+    }
+}
@@ -0,0 +1,268 @@
+use data::Frame;
+use failure::Error;
+use msgpack::*;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use EventType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum InstanceStatus {
+    /// The instance was created before the replayed part of the log, so whether it is still
+    /// active is only known once it completes or is canceled.
+    Unknown,
+    Active,
+    Completed,
+    Canceled,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenTask {
+    pub task_type: String,
+    pub activity_id: String,
+    pub state: TaskState,
+    pub retries: i32,
+    pub lock_time: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceState {
+    pub workflow_instance_key: i64,
+    pub bpmn_process_id: String,
+    pub version: i32,
+    pub workflow_key: i64,
+    pub status: InstanceStatus,
+    pub created_position: Option<u64>,
+    /// Active activities by activity instance key.
+    pub active_activities: BTreeMap<u64, String>,
+    pub payload: Value,
+    /// Created but not yet completed or canceled tasks by task key.
+    pub open_tasks: BTreeMap<u64, OpenTask>,
+    /// The activity of the latest activity lifecycle event, sequence flows, gateways and start or
+    /// end events are not activities.
+    pub last_activity: Option<String>,
+    pub last_position: u64,
+}
+
+impl InstanceState {
+    fn new(workflow_instance_key: i64, bpmn_process_id: &str, version: i32, workflow_key: i64) -> Self {
+        InstanceState {
+            workflow_instance_key,
+            bpmn_process_id: bpmn_process_id.to_string(),
+            version,
+            workflow_key,
+            status: InstanceStatus::Unknown,
+            created_position: None,
+            active_activities: BTreeMap::new(),
+            payload: Value::Null,
+            open_tasks: BTreeMap::new(),
+            last_activity: None,
+            last_position: 0,
+        }
+    }
+
+    fn apply_workflow_instance(&mut self, position: u64, key: u64, event: WorkflowInstanceEvent) -> Result<(), Error> {
+        use msgpack::WorkflowInstanceState::*;
+
+        self.last_position = position;
+
+        let activity_id = event.activity_id.clone().and_then(|a| if a.is_empty() { None } else { Some(a) });
+        match event.state {
+            ActivityReady | ActivityActivated | ActivityCompleting | ActivityCompleted | ActivityTerminated => {
+                if let Some(ref activity_id) = activity_id {
+                    self.last_activity = Some(activity_id.clone());
+                }
+            }
+            _ => {}
+        }
+
+        match event.state {
+            WorkflowInstanceCreated => {
+                self.status = InstanceStatus::Active;
+                self.created_position = Some(position);
+                self.version = event.version;
+                self.workflow_key = event.workflow_key;
+                self.payload = event.payload_json()?;
+            }
+            WorkflowInstanceRejected => self.status = InstanceStatus::Rejected,
+            ActivityReady | ActivityActivated => {
+                self.active_activities.insert(key, activity_id.unwrap_or_default());
+            }
+            ActivityCompleted | ActivityTerminated => {
+                self.active_activities.remove(&key);
+                if event.state == ActivityCompleted {
+                    self.payload = event.payload_json()?;
+                }
+            }
+            PayloadUpdated => self.payload = event.payload_json()?,
+            WorkflowInstanceCompleted => {
+                self.status = InstanceStatus::Completed;
+                self.active_activities.clear();
+                self.payload = event.payload_json()?;
+            }
+            WorkflowInstanceCanceled => {
+                self.status = InstanceStatus::Canceled;
+                self.active_activities.clear();
+                self.open_tasks.clear();
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn apply_task(&mut self, position: u64, key: u64, event: TaskEvent) {
+        use msgpack::TaskState::*;
+
+        self.last_position = position;
+
+        match event.state {
+            Created => {
+                self.open_tasks.insert(
+                    key,
+                    OpenTask {
+                        task_type: event.task_type,
+                        activity_id: event.headers.activity_id,
+                        state: event.state,
+                        retries: event.retries,
+                        lock_time: event.lock_time,
+                    },
+                );
+            }
+            Locked | LockExpired | Failed | RetriesUpdated => {
+                if let Some(task) = self.open_tasks.get_mut(&key) {
+                    task.state = event.state;
+                    task.retries = event.retries;
+                    task.lock_time = event.lock_time;
+                }
+            }
+            Completed | Canceled => {
+                self.open_tasks.remove(&key);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The materialised state of all workflow instances after replaying the log.
+#[derive(Debug, Default, Serialize)]
+pub struct State {
+    pub instances: BTreeMap<i64, InstanceState>,
+}
+
+impl State {
+    pub fn instance(&self, workflow_instance_key: i64) -> Option<&InstanceState> {
+        self.instances.get(&workflow_instance_key)
+    }
+
+    pub fn with_status(&self, status: InstanceStatus) -> Vec<&InstanceState> {
+        self.instances.values().filter(|i| i.status == status).collect()
+    }
+
+    /// Returns the instances which were created but never completed or canceled, grouped by
+    /// BPMN process id and version.
+    pub fn unfinished(&self) -> BTreeMap<(String, i32), Vec<&InstanceState>> {
+        let mut unfinished = BTreeMap::new();
+        for instance in self.instances.values() {
+            if instance.created_position.is_some() && instance.status == InstanceStatus::Active {
+                unfinished
+                    .entry((instance.bpmn_process_id.clone(), instance.version))
+                    .or_insert_with(Vec::new)
+                    .push(instance);
+            }
+        }
+        unfinished
+    }
+}
+
+/// Collects workflow instance and task events of one or more segments and replays them in
+/// position order to reconstruct the current workflow instance state.
+#[derive(Debug, Default)]
+pub struct StateEngine {
+    events: Vec<(u64, u64, Event)>,
+}
+
+impl StateEngine {
+    pub fn new() -> Self {
+        StateEngine::default()
+    }
+
+    pub fn add(&mut self, frame: &Frame) -> Result<(), Error> {
+        let event_type: EventType = frame.entry.metadata.event_type.into();
+        match event_type {
+            EventType::WorkflowInstance | EventType::Task => {
+                if let Some(event) = Event::decode(&event_type, frame.entry.event)? {
+                    self.events.push((frame.entry.log_entry.position, frame.entry.log_entry.key, event));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn replay(mut self) -> Result<State, Error> {
+        self.events.sort_by_key(|&(position, _, _)| position);
+
+        let mut state = State::default();
+
+        for (position, key, event) in self.events {
+            match event {
+                Event::WorkflowInstance(event) => {
+                    if event.state == WorkflowInstanceState::CreateWorkflowInstance {
+                        continue;
+                    }
+                    let instance_key = if event.workflow_instance_key < 0 {
+                        key as i64
+                    } else {
+                        event.workflow_instance_key
+                    };
+                    // a rejected cancel or payload update doesn't prove that the instance exists,
+                    // only a rejected creation is kept as a rejected instance
+                    if event.state.is_rejection() && event.state != WorkflowInstanceState::WorkflowInstanceRejected {
+                        if let Some(instance) = state.instances.get_mut(&instance_key) {
+                            instance.apply_workflow_instance(position, key, event)?;
+                        }
+                        continue;
+                    }
+                    state
+                        .instances
+                        .entry(instance_key)
+                        .or_insert_with(|| {
+                            InstanceState::new(instance_key, &event.bpmn_process_id, event.version, event.workflow_key)
+                        })
+                        .apply_workflow_instance(position, key, event)?;
+                }
+                Event::Task(event) => {
+                    let instance_key = event.headers.workflow_instance_key;
+                    if instance_key < 0 {
+                        continue;
+                    }
+                    if event.state.is_rejection() {
+                        if let Some(instance) = state.instances.get_mut(&instance_key) {
+                            instance.apply_task(position, key, event);
+                        }
+                        continue;
+                    }
+                    state
+                        .instances
+                        .entry(instance_key)
+                        .or_insert_with(|| {
+                            InstanceState::new(
+                                instance_key,
+                                &event.headers.bpmn_process_id,
+                                event.headers.workflow_definition_version,
+                                event.headers.workflow_key,
+                            )
+                        })
+                        .apply_task(position, key, event);
+                }
+                Event::Workflow(_) => {}
+            }
+        }
+
+        Ok(state)
+    }
+}
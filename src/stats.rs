@@ -0,0 +1,132 @@
+use data::Frame;
+use failure::Error;
+use msgpack::Event;
+use std::collections::BTreeMap;
+use {EventType, Producer};
+
+/// Size distribution with power of two buckets, each bucket counts the values up to its bound.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Distribution {
+    pub count: u64,
+    pub total: u64,
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+    pub buckets: BTreeMap<u64, u64>,
+}
+
+impl Distribution {
+    pub fn add(&mut self, value: u64) {
+        self.count += 1;
+        self.total += value;
+        self.min = Some(self.min.map_or(value, |m| m.min(value)));
+        self.max = Some(self.max.map_or(value, |m| m.max(value)));
+        *self.buckets.entry(value.next_power_of_two()).or_insert(0) += 1;
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.count > 0 {
+            Some(self.total as f64 / self.count as f64)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentTotals {
+    pub frames: u64,
+    pub bytes: u64,
+    pub first_position: Option<u64>,
+    pub last_position: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statistics {
+    pub frames: u64,
+    pub event_types: BTreeMap<String, u64>,
+    pub states: BTreeMap<String, u64>,
+    pub producers: BTreeMap<String, u64>,
+    pub workflows: BTreeMap<String, u64>,
+    pub task_types: BTreeMap<String, u64>,
+    pub raft_terms: BTreeMap<u32, u64>,
+    pub event_sizes: Distribution,
+    pub payload_sizes: Distribution,
+    pub segments: BTreeMap<String, SegmentTotals>,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Statistics::default()
+    }
+
+    pub fn add(&mut self, segment: &str, frame: &Frame) -> Result<(), Error> {
+        let log_entry = frame.entry.log_entry;
+        let event_type: EventType = frame.entry.metadata.event_type.into();
+        let producer: Producer = log_entry.producer.into();
+        let event = Event::decode(&event_type, frame.entry.event)?;
+
+        self.frames += 1;
+        increment(&mut self.event_types, format!("{:?}", event_type));
+        increment(&mut self.producers, format!("{:?}", producer));
+        *self.raft_terms.entry(log_entry.raft_term).or_insert(0) += 1;
+        self.event_sizes.add(frame.entry.event.len() as u64);
+
+        if let Some(ref event) = event {
+            if let Some(state) = event.state() {
+                increment(&mut self.states, format!("{:?} {}", event_type, state));
+            }
+
+            let version = match *event {
+                Event::Task(ref e) => e.headers.workflow_definition_version,
+                Event::Workflow(ref e) => e.version,
+                Event::WorkflowInstance(ref e) => e.version,
+            };
+            if let Some(bpmn_process_id) = event.bpmn_process_id() {
+                increment(&mut self.workflows, format!("{}:{}", bpmn_process_id, version));
+            }
+
+            if let Event::Task(ref e) = *event {
+                increment(&mut self.task_types, e.task_type.clone());
+            }
+
+            if let Some(payload) = event.payload() {
+                self.payload_sizes.add(payload.len() as u64);
+            }
+        }
+
+        let totals = self.segments.entry(segment.to_string()).or_insert_with(SegmentTotals::default);
+        totals.frames += 1;
+        totals.bytes += frame.data_frame.length as u64;
+        totals.first_position = totals.first_position.or(Some(log_entry.position));
+        totals.last_position = Some(log_entry.position);
+
+        Ok(())
+    }
+
+    /// Returns the named counters of the statistics.
+    pub fn counters(&self) -> Vec<(&'static str, Vec<(String, u64)>)> {
+        let raft_terms = self.raft_terms.iter().map(|(t, c)| (t.to_string(), *c)).collect();
+        vec![
+            ("eventType", sorted(&self.event_types)),
+            ("state", sorted(&self.states)),
+            ("producer", sorted(&self.producers)),
+            ("workflow", sorted(&self.workflows)),
+            ("taskType", sorted(&self.task_types)),
+            ("raftTerm", raft_terms),
+        ]
+    }
+}
+
+fn increment(counter: &mut BTreeMap<String, u64>, key: String) {
+    *counter.entry(key).or_insert(0) += 1;
+}
+
+/// Sorts the counts by descending count and then by name.
+fn sorted(counter: &BTreeMap<String, u64>) -> Vec<(String, u64)> {
+    let mut counts: Vec<(String, u64)> = counter.iter().map(|(k, c)| (k.clone(), *c)).collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
@@ -0,0 +1,162 @@
+use data::Frame;
+use failure::Error;
+use msgpack::*;
+use std::collections::BTreeMap;
+use EventType;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskTransition {
+    pub position: u64,
+    pub state: TaskState,
+    pub retries: i32,
+}
+
+/// Everything that happened to a single task, identified by its key.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskLifecycle {
+    pub key: u64,
+    pub task_type: String,
+    pub workflow_instance_key: i64,
+    pub activity_id: String,
+    pub transitions: Vec<TaskTransition>,
+    pub locks: usize,
+    pub lock_expirations: usize,
+    pub failures: usize,
+    pub completed: bool,
+    pub canceled: bool,
+}
+
+impl TaskLifecycle {
+    fn new(key: u64, event: &TaskEvent) -> Self {
+        TaskLifecycle {
+            key,
+            task_type: event.task_type.clone(),
+            workflow_instance_key: event.headers.workflow_instance_key,
+            activity_id: event.headers.activity_id.clone(),
+            transitions: Vec::new(),
+            locks: 0,
+            lock_expirations: 0,
+            failures: 0,
+            completed: false,
+            canceled: false,
+        }
+    }
+
+    fn add(&mut self, position: u64, event: &TaskEvent) {
+        let transition = TaskTransition {
+            position,
+            state: event.state,
+            retries: event.retries,
+        };
+        let index = match self.transitions.binary_search_by_key(&position, |t| t.position) {
+            Ok(_) => return,
+            Err(index) => index,
+        };
+        self.transitions.insert(index, transition);
+
+        match event.state {
+            TaskState::Locked => self.locks += 1,
+            TaskState::LockExpired => self.lock_expirations += 1,
+            TaskState::Failed => self.failures += 1,
+            TaskState::Completed => self.completed = true,
+            TaskState::Canceled => self.canceled = true,
+            _ => {}
+        }
+    }
+
+    pub fn retries(&self) -> Option<i32> {
+        self.transitions.last().map(|t| t.retries)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.completed || self.canceled
+    }
+
+    /// A task exhausted its retries if it failed and has no retries left.
+    pub fn retries_exhausted(&self) -> bool {
+        !self.is_finished() && self.failures > 0 && self.retries().map_or(false, |r| r <= 0)
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskTypeTotals {
+    pub tasks: usize,
+    pub locks: usize,
+    pub lock_expirations: usize,
+    pub failures: usize,
+    pub completed: usize,
+    pub canceled: usize,
+    pub retries_exhausted: usize,
+    pub unfinished: usize,
+}
+
+/// Follows the lifecycle of every task in the log to find misbehaving task workers.
+#[derive(Debug, Default)]
+pub struct TaskAnalysis {
+    tasks: BTreeMap<u64, TaskLifecycle>,
+}
+
+impl TaskAnalysis {
+    pub fn new() -> Self {
+        TaskAnalysis::default()
+    }
+
+    pub fn add(&mut self, frame: &Frame) -> Result<(), Error> {
+        if let EventType::Task = frame.entry.metadata.event_type.into() {
+            let event: TaskEvent = deserialize(frame.entry.event)?;
+            let key = frame.entry.log_entry.key;
+            self.tasks
+                .entry(key)
+                .or_insert_with(|| TaskLifecycle::new(key, &event))
+                .add(frame.entry.log_entry.position, &event);
+        }
+        Ok(())
+    }
+
+    pub fn task(&self, key: u64) -> Option<&TaskLifecycle> {
+        self.tasks.get(&key)
+    }
+
+    pub fn tasks(&self) -> Vec<&TaskLifecycle> {
+        self.tasks.values().collect()
+    }
+
+    pub fn retries_exhausted(&self) -> Vec<&TaskLifecycle> {
+        self.tasks.values().filter(|t| t.retries_exhausted()).collect()
+    }
+
+    pub fn repeated_lock_expirations(&self, min: usize) -> Vec<&TaskLifecycle> {
+        self.tasks.values().filter(|t| t.lock_expirations >= min).collect()
+    }
+
+    pub fn unfinished(&self) -> Vec<&TaskLifecycle> {
+        self.tasks.values().filter(|t| !t.is_finished()).collect()
+    }
+
+    pub fn totals(&self) -> BTreeMap<String, TaskTypeTotals> {
+        let mut totals: BTreeMap<String, TaskTypeTotals> = BTreeMap::new();
+        for task in self.tasks.values() {
+            let total = totals.entry(task.task_type.clone()).or_insert_with(TaskTypeTotals::default);
+            total.tasks += 1;
+            total.locks += task.locks;
+            total.lock_expirations += task.lock_expirations;
+            total.failures += task.failures;
+            if task.completed {
+                total.completed += 1;
+            }
+            if task.canceled {
+                total.canceled += 1;
+            }
+            if task.retries_exhausted() {
+                total.retries_exhausted += 1;
+            }
+            if !task.is_finished() {
+                total.unfinished += 1;
+            }
+        }
+        totals
+    }
+}
@@ -0,0 +1,92 @@
+use data::Frame;
+use failure::Error;
+use msgpack::*;
+use EventType;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RaftConfiguration {
+    pub position: u64,
+    pub members: Vec<RaftMember>,
+}
+
+/// A run of consecutive frames written in the same raft term.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Term {
+    pub term: u32,
+    pub segment: String,
+    pub first_position: u64,
+    pub last_position: u64,
+    pub frames: usize,
+    /// Raft events written in this term. The first one is written by the leader of the term.
+    pub configurations: Vec<RaftConfiguration>,
+    /// The term is lower than the highest term before it.
+    pub regression: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TermHistory {
+    pub terms: Vec<Term>,
+}
+
+impl TermHistory {
+    pub fn new() -> Self {
+        TermHistory::default()
+    }
+
+    pub fn add(&mut self, segment: &str, frame: &Frame) -> Result<(), Error> {
+        let term = frame.entry.log_entry.raft_term;
+        let position = frame.entry.log_entry.position;
+
+        let previous_term = self.terms.last().map(|t| t.term);
+        if previous_term != Some(term) {
+            let highest_term = self.terms.iter().map(|t| t.term).max();
+            self.terms.push(Term {
+                term,
+                segment: segment.to_string(),
+                first_position: position,
+                last_position: position,
+                frames: 0,
+                configurations: Vec::new(),
+                regression: highest_term.map(|h| term < h).unwrap_or(false),
+            });
+        }
+
+        let event_type: EventType = frame.entry.metadata.event_type.into();
+        let configuration = match event_type {
+            EventType::Raft => {
+                let event: RaftEvent = deserialize(frame.entry.event)?;
+                Some(RaftConfiguration {
+                    position,
+                    members: event.members,
+                })
+            }
+            _ => None,
+        };
+
+        if let Some(current) = self.terms.last_mut() {
+            current.last_position = position;
+            current.frames += 1;
+            current.configurations.extend(configuration);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the terms which are lower than a term before them, each together with the first
+    /// run of the highest term before it.
+    pub fn regressions(&self) -> Vec<(&Term, &Term)> {
+        let mut regressions = Vec::new();
+        let mut highest: Option<&Term> = None;
+        for term in &self.terms {
+            match highest {
+                Some(h) if term.term < h.term => regressions.push((h, term)),
+                Some(h) if term.term == h.term => {}
+                _ => highest = Some(term),
+            }
+        }
+        regressions
+    }
+}
@@ -0,0 +1,106 @@
+use data::Frame;
+use failure::Error;
+use msgpack::*;
+use serde_json::Value;
+use EventType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum TimelineState {
+    WorkflowInstance(WorkflowInstanceState),
+    Task(TaskState),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEntry {
+    pub position: u64,
+    pub key: u64,
+    pub state: TimelineState,
+    pub activity_id: Option<String>,
+    pub task_type: Option<String>,
+    /// The payload of the event, only set if it differs from the last payload of the timeline.
+    pub payload: Option<Value>,
+}
+
+/// Collects the workflow instance and task events of a single workflow instance, which can be
+/// spread over multiple segments.
+#[derive(Debug)]
+pub struct Timeline {
+    workflow_instance_key: i64,
+    entries: Vec<TimelineEntry>,
+}
+
+impl Timeline {
+    pub fn new(workflow_instance_key: i64) -> Self {
+        Timeline {
+            workflow_instance_key,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, frame: &Frame) -> Result<bool, Error> {
+        let position = frame.entry.log_entry.position;
+        let key = frame.entry.log_entry.key;
+
+        let entry = match frame.entry.metadata.event_type.into() {
+            EventType::WorkflowInstance => {
+                let event: WorkflowInstanceEvent = deserialize(frame.entry.event)?;
+                if event.workflow_instance_key != self.workflow_instance_key &&
+                    key as i64 != self.workflow_instance_key
+                {
+                    return Ok(false);
+                }
+                TimelineEntry {
+                    position,
+                    key,
+                    state: TimelineState::WorkflowInstance(event.state),
+                    activity_id: event.activity_id.clone().and_then(|a| if a.is_empty() { None } else { Some(a) }),
+                    task_type: None,
+                    payload: Some(event.payload_json()?),
+                }
+            }
+            EventType::Task => {
+                let event: TaskEvent = deserialize(frame.entry.event)?;
+                if event.headers.workflow_instance_key != self.workflow_instance_key {
+                    return Ok(false);
+                }
+                TimelineEntry {
+                    position,
+                    key,
+                    state: TimelineState::Task(event.state),
+                    activity_id: Some(event.headers.activity_id.clone()),
+                    task_type: Some(event.task_type.clone()),
+                    payload: Some(event.payload_json()?),
+                }
+            }
+            _ => return Ok(false),
+        };
+
+        self.entries.push(entry);
+        Ok(true)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the collected entries ordered by position, payloads are only kept where they
+    /// changed.
+    pub fn finish(mut self) -> Vec<TimelineEntry> {
+        self.entries.sort_by_key(|e| e.position);
+
+        let mut last_payload = Value::Null;
+        for entry in &mut self.entries {
+            match entry.payload.take() {
+                Some(payload) => if !payload.is_null() && payload != last_payload {
+                    entry.payload = Some(payload.clone());
+                    last_payload = payload;
+                },
+                None => {}
+            }
+        }
+
+        self.entries
+    }
+}